@@ -1,7 +1,14 @@
+use std::collections::HashMap;
 use std::io;
 
+use freetype::face::KerningMode;
 use thiserror::Error;
 
+mod sfnt;
+pub mod subset;
+
+use subset::Subset;
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("I/O error")]
@@ -12,10 +19,28 @@ pub enum Error {
     Png(#[from] lodepng::ffi::Error),
     #[error("unsupported format")]
     UnsupportedFormat,
+    #[error("replacement character {0:?} is not contained in the font")]
+    MissingReplacement(char),
+    #[cfg(feature = "fluent")]
+    #[error("Fluent syntax error")]
+    Fluent(Vec<fluent_syntax::parser::ParserError>),
+    #[cfg(feature = "gettext")]
+    #[error("gettext catalog error")]
+    Gettext(#[from] polib::po_file::POParseError),
+    #[cfg(feature = "json")]
+    #[error("JSON error")]
+    Json(#[from] serde_json::Error),
+    #[error("locales {0:?} and {1:?} result in the same font name")]
+    LocaleCollision(String, String),
+    #[error("locale {0:?} contains characters which are missing in the font: {1:?}")]
+    MissingGlyphs(String, Vec<char>),
 }
 
 pub struct Font {
     face: freetype::Face,
+    replacement: Option<char>,
+    /// Glyph pairs listed in the kerning table, if the table could be read.
+    kerning_pairs: Option<Vec<(u32, u32)>>,
 }
 
 impl Font {
@@ -23,9 +48,24 @@ impl Font {
         use freetype::Library;
         let lib = Library::init().unwrap();
         let face = lib.new_face(path, 0)?;
-        Ok(Font { face: face })
+        let kerning_pairs = sfnt::kerning_pairs(&std::fs::read(path)?);
+        Ok(Font {
+            face,
+            replacement: None,
+            kerning_pairs,
+        })
     }
 
+    /// Includes the character in all generated fonts and draws it in place of missing
+    /// characters.
+    pub fn replacement(&mut self, c: char) {
+        self.replacement = Some(c);
+    }
+
+    /// Generates a font containing the characters of the string.
+    ///
+    /// Characters which are not contained in the font file are drawn with its default glyph.
+    /// Panics if FreeType fails to process the font, use `generate_subset()` to handle errors.
     pub fn generate(
         &mut self,
         name: &str,
@@ -34,27 +74,118 @@ impl Font {
         type_: FontType,
         gfx_crate: &str,
     ) -> String {
-        let mut subset = subset.chars().collect::<Vec<_>>();
+        let chars = subset.chars().collect();
+        self.generate_chars(name, size, chars, type_, gfx_crate)
+            .unwrap()
+    }
+
+    /// Generates one font for each locale in a directory of translation catalogs (see
+    /// `Subset::locales()`), containing the characters of the locale and of `common`.
+    ///
+    /// The constants are named after the font and the locale, e.g., `FONT_DE_DE` for the
+    /// locale `de-DE`. Returns an error if two locales result in the same name, e.g., `de-DE`
+    /// and `de_DE`, or if a catalog contains characters which are missing in the font.
+    /// Characters of `common` which are missing in the font are skipped.
+    pub fn generate_locales(
+        &mut self,
+        name: &str,
+        size: isize,
+        dir: &str,
+        common: &Subset,
+        type_: FontType,
+        gfx_crate: &str,
+    ) -> Result<String, Error> {
+        let mut code = String::new();
+        let mut names = HashMap::new();
+        for (locale, subset) in Subset::locales(dir)? {
+            let suffix = locale
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() {
+                        c.to_ascii_uppercase()
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>();
+            let name = format!("{}_{}", name, suffix);
+            if let Some(other) = names.insert(name.clone(), locale.clone()) {
+                return Err(Error::LocaleCollision(other, locale));
+            }
+            let missing = subset
+                .iter()
+                .filter(|&c| self.face.get_char_index(c as usize) == 0)
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                return Err(Error::MissingGlyphs(locale, missing));
+            }
+            let subset = subset.union(common);
+            code += &self.generate_subset(&name, size, &subset, type_, gfx_crate)?;
+        }
+        Ok(code)
+    }
+
+    /// Generates a font containing the characters of the subset.
+    ///
+    /// Characters which are not contained in the font file are skipped, but the replacement
+    /// character has to be contained.
+    pub fn generate_subset(
+        &mut self,
+        name: &str,
+        size: isize,
+        subset: &Subset,
+        type_: FontType,
+        gfx_crate: &str,
+    ) -> Result<String, Error> {
+        if let Some(c) = self.replacement {
+            if self.face.get_char_index(c as usize) == 0 {
+                return Err(Error::MissingReplacement(c));
+            }
+        }
+        let chars = subset
+            .iter()
+            .filter(|&c| self.face.get_char_index(c as usize) != 0)
+            .collect();
+        self.generate_chars(name, size, chars, type_, gfx_crate)
+    }
+
+    fn generate_chars(
+        &mut self,
+        name: &str,
+        size: isize,
+        mut subset: Vec<char>,
+        type_: FontType,
+        gfx_crate: &str,
+    ) -> Result<String, Error> {
+        subset.extend(self.replacement);
         subset.sort();
+        subset.dedup();
+        let replacement = self.replacement.map(|c| subset.binary_search(&c).unwrap());
         // Set the resultion to 72dpi so that a point equals a pixel.
-        self.face.set_char_size(0, size * 64, 72, 72).unwrap();
-        // Generate all glyphs.
+        self.face.set_char_size(0, size * 64, 72, 72)?;
+        // Generate all glyphs, with the image data stored in shared arrays.
+        let mut data = SharedData::new(name, gfx_crate);
         let mut glyphs = Vec::new();
         for c in subset.iter() {
-            glyphs.push(self.generate_glyph(*c, type_, gfx_crate));
+            glyphs.push(self.generate_glyph(*c, type_, &mut data, gfx_crate)?);
         }
         // Generate the font.
         let size = self.face.size_metrics().unwrap();
-        format!(
-            "pub const {}: {}::font::Font<{}> = {}::font::Font {{
+        let kerning = self.generate_kerning(&subset, gfx_crate)?;
+        Ok(format!(
+            "{}pub const {}: {}::font::Font<{}> = {}::font::Font {{
     ascender: {},
     descender: {},
     glyphs: &[
         {}
     ],
-    get_glyph_index: {},
+    glyph_index: {},
+    kerning: &[{}],
+    replacement: {:?},
+    fallback: None,
 }};
 ",
+            data.generate(),
             name,
             gfx_crate,
             type_.get_image_type(gfx_crate),
@@ -62,102 +193,195 @@ impl Font {
             (size.ascender + 63) / 64,
             -(size.descender + 63) / 64,
             glyphs.join(",\n        "),
-            Self::generate_get_glyph_index(subset),
-        )
+            Self::generate_glyph_ranges(&subset, gfx_crate),
+            kerning,
+            replacement,
+        ))
     }
 
-    fn generate_get_glyph_index(chars: Vec<char>) -> String {
-        let mut code = "".to_string();
-        let mut run_start = chars[0] as u32;
-        let mut run_length = 1;
-        for i in 1..chars.len() {
-            let c = chars[i] as u32;
-            if c == run_start + run_length {
-                run_length += 1;
-            } else {
-                code += &Self::generate_get_glyph_index_range(
-                    run_start,
-                    run_length,
-                    i - run_length as usize,
-                );
-                run_start = c;
-                run_length = 1;
+    /// Generates the kerning pairs between the glyphs of the subset, sorted by the glyph
+    /// indices.
+    ///
+    /// Only the pairs listed in the kerning table are queried, unless the table could not be
+    /// read, in which case all pairs of the subset are checked.
+    fn generate_kerning(&self, chars: &[char], gfx_crate: &str) -> Result<String, Error> {
+        if !self.face.has_kerning() {
+            return Ok("".to_string());
+        }
+        let indices = chars
+            .iter()
+            .map(|&c| self.face.get_char_index(c as usize))
+            .collect::<Vec<_>>();
+        let candidates = match &self.kerning_pairs {
+            Some(kerning_pairs) => {
+                // Multiple characters can share a glyph.
+                let mut positions = HashMap::<u32, Vec<usize>>::new();
+                for (position, &index) in indices.iter().enumerate() {
+                    positions.entry(index).or_default().push(position);
+                }
+                let mut candidates = Vec::new();
+                for (left_index, right_index) in kerning_pairs {
+                    if let (Some(left), Some(right)) =
+                        (positions.get(left_index), positions.get(right_index))
+                    {
+                        for &left in left {
+                            candidates.extend(right.iter().map(|&right| (left, right)));
+                        }
+                    }
+                }
+                candidates.sort_unstable();
+                candidates
+            }
+            None => (0..indices.len())
+                .flat_map(|left| (0..indices.len()).map(move |right| (left, right)))
+                .collect(),
+        };
+        let mut pairs = Vec::new();
+        for (left, right) in candidates {
+            let kerning = self.face.get_kerning(
+                indices[left],
+                indices[right],
+                KerningMode::KerningDefault,
+            )?;
+            // Round the 26.6 fixed-point value to full pixels.
+            let offset = (kerning.x + 32) >> 6;
+            if offset != 0 {
+                pairs.push(format!(
+                    "
+        {}::font::Kerning {{
+            left: {},
+            right: {},
+            offset: {},
+        }},",
+                    gfx_crate, left, right, offset
+                ));
             }
         }
-        code += &Self::generate_get_glyph_index_range(
-            run_start,
-            run_length,
-            chars.len() - run_length as usize,
-        );
-
-        format!(
-            "|c: char| -> Option<usize> {{
-        let c = c as usize;
-        {}None
-    }}",
-            code
-        )
+        if pairs.is_empty() {
+            return Ok("".to_string());
+        }
+        Ok(pairs.concat() + "\n    ")
     }
 
-    fn generate_get_glyph_index_range(
-        run_start: u32,
-        run_length: u32,
-        start_index: usize,
-    ) -> String {
-        if run_length == 1 {
-            format!(
-                "if c == {} {{
-            return Some({});
-        }}
-        ",
-                run_start, start_index
-            )
-        } else {
-            format!(
-                "if c >= {} && c < {} {{
-            return Some({} + c - {});
-        }}
-        ",
-                run_start,
-                run_start + run_length,
-                start_index,
-                run_start
-            )
+    /// Generates a table of the ranges of consecutive characters, which is searched via binary
+    /// search.
+    fn generate_glyph_ranges(chars: &[char], gfx_crate: &str) -> String {
+        assert!(chars.len() <= u16::MAX as usize, "too many glyphs");
+        let mut ranges: Vec<(u32, usize, usize)> = Vec::new();
+        for (index, &c) in chars.iter().enumerate() {
+            let c = c as u32;
+            match ranges.last_mut() {
+                Some((start, len, _)) if *start + *len as u32 == c && *len < u16::MAX as usize => {
+                    *len += 1
+                }
+                _ => ranges.push((c, 1, index)),
+            }
+        }
+        let mut code = String::new();
+        for (start, len, index) in ranges {
+            code += &format!(
+                "
+        {}::font::GlyphRange {{
+            start: {},
+            len: {},
+            index: {},
+        }},",
+                gfx_crate, start, len, index
+            );
         }
+        format!("{}::font::GlyphIndex::Ranges(&[{}\n    ])", gfx_crate, code)
     }
 
-    fn generate_glyph(&mut self, c: char, type_: FontType, gfx_crate: &str) -> String {
+    fn generate_glyph(
+        &mut self,
+        c: char,
+        type_: FontType,
+        data: &mut SharedData,
+        gfx_crate: &str,
+    ) -> Result<String, Error> {
+        let target = match type_ {
+            FontType::RLE | FontType::Bitmap | FontType::NibbleRLE | FontType::Auto => {
+                freetype::face::LoadFlag::TARGET_MONO
+            }
+            FontType::Gray2 | FontType::Gray4 => freetype::face::LoadFlag::TARGET_NORMAL,
+        };
         self.face
-            .load_char(
-                c as usize,
-                freetype::face::LoadFlag::RENDER | freetype::face::LoadFlag::TARGET_MONO,
-            )
-            .unwrap();
+            .load_char(c as usize, freetype::face::LoadFlag::RENDER | target)?;
         let glyph = self.face.glyph();
-        let image = match type_ {
-            FontType::RLE => Self::generate_rle_image(&glyph.bitmap(), gfx_crate),
-            FontType::Bitmap => Self::generate_bitmap_image(&glyph.bitmap(), gfx_crate),
+        let bitmap = glyph.bitmap();
+        let (image, comment) = match type_ {
+            FontType::Gray2 => (
+                Self::generate_gray_image(&bitmap, 2, data, gfx_crate),
+                "".to_string(),
+            ),
+            FontType::Gray4 => (
+                Self::generate_gray_image(&bitmap, 4, data, gfx_crate),
+                "".to_string(),
+            ),
+            _ => {
+                // The sizes are shared by the comment and the automatic selection, as each
+                // one requires encoding the glyph in all formats.
+                let sizes = Self::encoding_sizes(&bitmap);
+                let smallest = Self::smallest_encoding(&sizes);
+                let image = match type_ {
+                    FontType::RLE => Self::generate_rle_image(&bitmap, data, gfx_crate),
+                    FontType::Bitmap => Self::generate_bitmap_image(&bitmap, data, gfx_crate),
+                    FontType::NibbleRLE => {
+                        Self::generate_nibble_rle_image(&bitmap, data, gfx_crate)
+                    }
+                    _ => Self::generate_auto_image(&bitmap, smallest, data, gfx_crate),
+                };
+                let comment = Self::generate_size_comment(c, &sizes, smallest);
+                (image, comment + "\n        ")
+            }
         };
         //assert!(glyph.bitmap_left() >= 0);
         assert!(glyph.bitmap_top() >= 0);
-        format!(
-            "{}::font::Glyph::<{}> {{
+        Ok(format!(
+            "{}{}::font::Glyph::<{}> {{
                 image: {},
                 image_left: {},
                 image_top: {},
                 advance: {},
         }}",
+            comment,
             gfx_crate,
             type_.get_image_type(gfx_crate),
             image,
             glyph.bitmap_left(),
             glyph.bitmap_top(),
             (glyph.advance().x + 63) / 64
+        ))
+    }
+
+    /// Returns the pixel data of the bitmap, which is empty for empty glyphs like ' '.
+    ///
+    /// FreeType does not allocate a buffer for those, and the null pointer must not be turned
+    /// into a slice.
+    fn bitmap_buffer(bm: &freetype::Bitmap) -> &[u8] {
+        if bm.width() == 0 || bm.rows() == 0 {
+            &[]
+        } else {
+            bm.buffer()
+        }
+    }
+
+    fn generate_rle_image(bm: &freetype::Bitmap, data: &mut SharedData, gfx_crate: &str) -> String {
+        format!(
+            "{}::image::MonoRLEImage {{
+                    data: {},
+                    width: {},
+                    height: {},
+                }}",
+            gfx_crate,
+            data.add_words(&Self::encode_rle(bm)),
+            bm.width(),
+            bm.rows()
         )
     }
 
-    fn generate_rle_image(bm: &freetype::Bitmap, gfx_crate: &str) -> String {
-        let buffer = bm.buffer();
+    fn encode_rle(bm: &freetype::Bitmap) -> Vec<u16> {
+        let buffer = Self::bitmap_buffer(bm);
         let pitch = bm.pitch() as usize;
         let width = bm.width() as usize;
         let height = bm.rows() as usize;
@@ -171,30 +395,11 @@ impl Font {
             Self::generate_rle(&mut data, row, width);
             data[y + 1] = data.len() as u16;
         }
-
-        let mut data_text = "[".to_string();
-        for i in 0..data.len() {
-            if (i & 15) == 0 {
-                data_text += "\n                        ";
-            }
-            data_text += &format!("{},", data[i]);
-            if i & 15 != 15 && i != data.len() - 1 {
-                data_text += " ";
-            }
-        }
-        data_text += "\n                    ]";
-        format!(
-            "{}::image::MonoRLEImage {{
-                    data: &{},
-                    width: {},
-                    height: {},
-                }}",
-            gfx_crate, data_text, width, height
-        )
+        data
     }
 
     fn generate_rle(output: &mut Vec<u16>, row: &[u8], width: usize) {
-        let mut run_color = (row[0] & 0x80) >> 7;
+        let mut run_color = row.first().map_or(0, |byte| (byte & 0x80) >> 7);
         let mut run_length = 0;
 
         let mut bits = 0;
@@ -216,8 +421,31 @@ impl Font {
         output.push(((run_color as u16) << 15) | run_length);
     }
 
-    fn generate_bitmap_image(bm: &freetype::Bitmap, gfx_crate: &str) -> String {
-        let buffer = bm.buffer();
+    fn generate_bitmap_image(
+        bm: &freetype::Bitmap,
+        data: &mut SharedData,
+        gfx_crate: &str,
+    ) -> String {
+        let (output_buffer, output_stride) = Self::encode_bitmap(bm);
+
+        format!(
+            "{}::image::MonoBitmapImage {{
+                    data: {},
+                    width: {},
+                    height: {},
+                    stride: {},
+                }}",
+            gfx_crate,
+            data.add_bytes(&output_buffer),
+            bm.width(),
+            bm.rows(),
+            output_stride
+        )
+    }
+
+    /// Returns the bitmap data and the stride.
+    fn encode_bitmap(bm: &freetype::Bitmap) -> (Vec<u8>, usize) {
+        let buffer = Self::bitmap_buffer(bm);
         let pitch = bm.pitch() as usize;
         let width = bm.width() as usize;
         let height = bm.rows() as usize;
@@ -232,15 +460,253 @@ impl Font {
                 &mut output_buffer[y * output_stride..y * output_stride + output_stride];
             output_row.copy_from_slice(input_row);
         }
+        (output_buffer, output_stride)
+    }
 
+    fn generate_nibble_rle_image(
+        bm: &freetype::Bitmap,
+        data: &mut SharedData,
+        gfx_crate: &str,
+    ) -> String {
         format!(
-            "{}::image::MonoBitmapImage {{
-                    data: &{:?},
+            "{}::image::MonoNibbleRLEImage {{
+                    data: {},
+                    width: {},
+                    height: {},
+                }}",
+            gfx_crate,
+            data.add_bytes(&Self::encode_nibble_rle(bm)),
+            bm.width(),
+            bm.rows()
+        )
+    }
+
+    /// Encodes the pixels of all rows as one sequence of runs with alternating colors,
+    /// starting with unset pixels. Each run length is stored in one nibble, longer runs are
+    /// split by inserting empty runs of the other color.
+    fn encode_nibble_rle(bm: &freetype::Bitmap) -> Vec<u8> {
+        let buffer = Self::bitmap_buffer(bm);
+        let pitch = bm.pitch() as usize;
+        let width = bm.width() as usize;
+        let height = bm.rows() as usize;
+
+        let mut nibbles = Vec::new();
+        let mut run_color = 0;
+        let mut run_length = 0;
+        for y in 0..height {
+            for x in 0..width {
+                let bit = (buffer[y * pitch + x / 8] >> (7 - x % 8)) & 1;
+                if bit != run_color {
+                    Self::push_nibble_run(&mut nibbles, run_length);
+                    run_color = bit;
+                    run_length = 0;
+                }
+                run_length += 1;
+            }
+        }
+        // Trailing unset pixels are implicit.
+        if run_color == 1 {
+            Self::push_nibble_run(&mut nibbles, run_length);
+        }
+
+        nibbles
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair.get(1).unwrap_or(&0))
+            .collect()
+    }
+
+    fn push_nibble_run(nibbles: &mut Vec<u8>, mut run_length: usize) {
+        while run_length > 15 {
+            nibbles.push(15);
+            nibbles.push(0);
+            run_length -= 15;
+        }
+        nibbles.push(run_length as u8);
+    }
+
+    /// Returns the data size of the glyph in each monochrome encoding.
+    fn encoding_sizes(bm: &freetype::Bitmap) -> [(FontType, usize); 3] {
+        [
+            (FontType::Bitmap, Self::encode_bitmap(bm).0.len()),
+            (FontType::RLE, Self::encode_rle(bm).len() * 2),
+            (FontType::NibbleRLE, Self::encode_nibble_rle(bm).len()),
+        ]
+    }
+
+    fn smallest_encoding(sizes: &[(FontType, usize); 3]) -> FontType {
+        sizes.iter().min_by_key(|(_, size)| *size).unwrap().0
+    }
+
+    /// Returns a comment which lists the size of the glyph in each monochrome encoding.
+    fn generate_size_comment(
+        c: char,
+        sizes: &[(FontType, usize); 3],
+        smallest: FontType,
+    ) -> String {
+        let name = |type_| match type_ {
+            FontType::Bitmap => "bitmap",
+            FontType::RLE => "RLE",
+            _ => "nibble RLE",
+        };
+        format!(
+            "// {:?}: {} (smallest: {})",
+            c,
+            sizes
+                .iter()
+                .map(|&(type_, size)| format!("{} {} bytes", name(type_), size))
+                .collect::<Vec<_>>()
+                .join(", "),
+            name(smallest)
+        )
+    }
+
+    /// Generates the image in the smallest encoding, as selected via `smallest_encoding()`.
+    fn generate_auto_image(
+        bm: &freetype::Bitmap,
+        smallest: FontType,
+        data: &mut SharedData,
+        gfx_crate: &str,
+    ) -> String {
+        let (variant, image) = match smallest {
+            FontType::Bitmap => ("Bitmap", Self::generate_bitmap_image(bm, data, gfx_crate)),
+            FontType::RLE => ("RLE", Self::generate_rle_image(bm, data, gfx_crate)),
+            _ => (
+                "NibbleRLE",
+                Self::generate_nibble_rle_image(bm, data, gfx_crate),
+            ),
+        };
+        format!(
+            "{}::image::MonoGlyphImage::{}({})",
+            gfx_crate, variant, image
+        )
+    }
+
+    fn generate_gray_image(
+        bm: &freetype::Bitmap,
+        bits_per_pixel: usize,
+        data: &mut SharedData,
+        gfx_crate: &str,
+    ) -> String {
+        let buffer = Self::bitmap_buffer(bm);
+        let pitch = bm.pitch() as usize;
+        let width = bm.width() as usize;
+        let height = bm.rows() as usize;
+        let max = (1 << bits_per_pixel) - 1;
+
+        let output_stride = (width * bits_per_pixel).div_ceil(8);
+        let mut output_buffer = vec![0u8; output_stride * height];
+
+        for y in 0..height {
+            for x in 0..width {
+                // Quantize the 8-bit coverage, the first pixel is stored in the most
+                // significant bits.
+                let value = (buffer[y * pitch + x] as u32 * max + 127) / 255;
+                let bit = x * bits_per_pixel;
+                output_buffer[y * output_stride + bit / 8] |=
+                    (value << (8 - bits_per_pixel - bit % 8)) as u8;
+            }
+        }
+
+        format!(
+            "{}::image::GrayBitmapImage {{
+                    data: {},
                     width: {},
                     height: {},
                     stride: {},
+                    bits_per_pixel: {},
                 }}",
-            gfx_crate, output_buffer, width, height, output_stride
+            gfx_crate,
+            data.add_bytes(&output_buffer),
+            width,
+            height,
+            output_stride,
+            bits_per_pixel
+        )
+    }
+}
+
+/// Arrays which contain the image data of all glyphs of a font, so that identical images are
+/// only stored once.
+struct SharedData {
+    name: String,
+    gfx_crate: String,
+    bytes: Vec<u8>,
+    words: Vec<u16>,
+    byte_offsets: HashMap<Vec<u8>, usize>,
+    word_offsets: HashMap<Vec<u16>, usize>,
+}
+
+impl SharedData {
+    fn new(font_name: &str, gfx_crate: &str) -> SharedData {
+        SharedData {
+            name: font_name.to_string(),
+            gfx_crate: gfx_crate.to_string(),
+            bytes: Vec::new(),
+            words: Vec::new(),
+            byte_offsets: HashMap::new(),
+            word_offsets: HashMap::new(),
+        }
+    }
+
+    /// Adds the data unless it is already contained and returns an expression for the slice.
+    fn add_bytes(&mut self, data: &[u8]) -> String {
+        let bytes = &mut self.bytes;
+        let offset = *self.byte_offsets.entry(data.to_vec()).or_insert_with(|| {
+            bytes.extend_from_slice(data);
+            bytes.len() - data.len()
+        });
+        self.slice("DATA", offset, data.len())
+    }
+
+    /// Adds the data unless it is already contained and returns an expression for the slice.
+    fn add_words(&mut self, data: &[u16]) -> String {
+        let words = &mut self.words;
+        let offset = *self.word_offsets.entry(data.to_vec()).or_insert_with(|| {
+            words.extend_from_slice(data);
+            words.len() - data.len()
+        });
+        self.slice("RLE_DATA", offset, data.len())
+    }
+
+    fn slice(&self, suffix: &str, offset: usize, len: usize) -> String {
+        format!(
+            "{}::image::shared_data(&{}_{}, {}, {})",
+            self.gfx_crate, self.name, suffix, offset, len
+        )
+    }
+
+    /// Generates the definitions of the arrays which were used.
+    fn generate(&self) -> String {
+        let mut code = String::new();
+        if !self.byte_offsets.is_empty() {
+            code += &Self::generate_array(&self.name, "DATA", "u8", &self.bytes);
+        }
+        if !self.word_offsets.is_empty() {
+            code += &Self::generate_array(&self.name, "RLE_DATA", "u16", &self.words);
+        }
+        code
+    }
+
+    fn generate_array<T: std::fmt::Display>(
+        name: &str,
+        suffix: &str,
+        type_: &str,
+        data: &[T],
+    ) -> String {
+        let mut data_text = String::new();
+        for line in data.chunks(16) {
+            data_text += "\n   ";
+            for value in line {
+                data_text += &format!(" {},", value);
+            }
+        }
+        format!(
+            "static {}_{}: [{}; {}] = [{}\n];\n",
+            name,
+            suffix,
+            type_,
+            data.len(),
+            data_text
         )
     }
 }
@@ -249,6 +715,14 @@ impl Font {
 pub enum FontType {
     RLE,
     Bitmap,
+    /// Runs of up to 15 pixels stored in one nibble each, without a row table.
+    NibbleRLE,
+    /// The smallest of the monochrome encodings, selected for each glyph.
+    Auto,
+    /// Anti-aliased glyphs with 2 bits of coverage per pixel.
+    Gray2,
+    /// Anti-aliased glyphs with 4 bits of coverage per pixel.
+    Gray4,
 }
 
 impl FontType {
@@ -256,6 +730,11 @@ impl FontType {
         match self {
             FontType::RLE => format!("{}::image::MonoRLEImage", gfx_crate),
             FontType::Bitmap => format!("{}::image::MonoBitmapImage", gfx_crate),
+            FontType::NibbleRLE => format!("{}::image::MonoNibbleRLEImage", gfx_crate),
+            FontType::Auto => format!("{}::image::MonoGlyphImage", gfx_crate),
+            FontType::Gray2 | FontType::Gray4 => {
+                format!("{}::image::GrayBitmapImage", gfx_crate)
+            }
         }
     }
 }
@@ -272,7 +751,7 @@ impl Image {
         let image = lodepng::decode32_file(path)?;
         let width = image.width;
         let height = image.height;
-        let stride = (width + 7) / 8;
+        let stride = width.div_ceil(8);
         let mut data = vec![0u8; (stride * height) as usize];
         for y in 0..height {
             for x in 0..width {
@@ -281,12 +760,12 @@ impl Image {
                 let alpha = pixel.a;
                 let level = (255 - avg_color) as u32 * alpha as u32 / 255;
                 if level < 128 {
-                    data[(y * stride + x / 8) as usize] |= 1 << (x & 7);
+                    data[(y * stride + x / 8) as usize] |= 0x80 >> (x & 7);
                 }
             }
         }
         Ok(Image {
-            data: data,
+            data,
             stride: stride as u32,
             width: width as u32,
             height: height as u32,
@@ -315,3 +794,140 @@ impl Image {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::sfnt::test_font;
+    use super::subset::Subset;
+    use super::{Error, Font, FontType};
+
+    /// Loads a font via a temporary file which is unique to the test.
+    fn load_font(test: &str, data: &[u8]) -> Font {
+        let name = format!("tinygfx-assets-{}-{}.ttf", test, process::id());
+        let path = env::temp_dir().join(name);
+        fs::write(&path, data).unwrap();
+        let font = Font::load(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        font
+    }
+
+    /// Removes all whitespace, so that the generated code can be compared more easily.
+    fn compact(code: &str) -> String {
+        code.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+
+    #[test]
+    fn test_kerning() {
+        let glyphs = [
+            ('A', [0, 0, 600, 700]),
+            ('V', [0, 0, 600, 700]),
+            ('o', [0, 0, 500, 500]),
+        ];
+        let data = test_font(&glyphs, &[('A', 'V', -200), ('V', 'o', -100)]);
+        let mut font = load_font("kerning", &data);
+        // FreeType reduces the kerning below 25 pixels per em.
+        let code = font
+            .generate_subset("FONT", 30, &Subset::from("oVA"), FontType::Bitmap, "gfx")
+            .unwrap();
+        let code = compact(&code);
+        assert_eq!(code.matches("gfx::font::Kerning{").count(), 2);
+        assert!(code.contains("gfx::font::Kerning{left:0,right:1,offset:-6,}"));
+        assert!(code.contains("gfx::font::Kerning{left:1,right:2,offset:-3,}"));
+
+        // Pairs with characters outside of the subset are skipped.
+        let code = font
+            .generate_subset("FONT", 30, &Subset::from("Ao"), FontType::Bitmap, "gfx")
+            .unwrap();
+        assert!(compact(&code).contains("kerning:&[],"));
+    }
+
+    #[test]
+    fn test_missing_replacement() {
+        let glyphs = [('A', [0, 0, 600, 700]), ('?', [0, 0, 500, 700])];
+        let mut font = load_font("missing-replacement", &test_font(&glyphs, &[]));
+        font.replacement('?');
+        let code = font
+            .generate_subset("FONT", 20, &Subset::from("AB"), FontType::Bitmap, "gfx")
+            .unwrap();
+        assert!(compact(&code).contains("replacement:Some(0),"));
+
+        font.replacement('\u{fffd}');
+        let result = font.generate_subset("FONT", 20, &Subset::from("A"), FontType::Bitmap, "gfx");
+        assert!(matches!(result, Err(Error::MissingReplacement('\u{fffd}'))));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn test_locales() {
+        let glyphs = [
+            ('A', [0, 0, 600, 700]),
+            ('V', [0, 0, 600, 700]),
+            ('o', [0, 0, 500, 500]),
+        ];
+        let mut font = load_font("locales", &test_font(&glyphs, &[]));
+        let dir = env::temp_dir().join(format!("tinygfx-assets-locales-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("fr-FR")).unwrap();
+        fs::write(dir.join("de.json"), r#"{"a": "A"}"#).unwrap();
+        fs::write(dir.join("fr-FR/main.json"), r#"{"a": "V"}"#).unwrap();
+        let generate = |font: &mut Font| {
+            let dir = dir.to_str().unwrap();
+            font.generate_locales("FONT", 20, dir, &"o".into(), FontType::Bitmap, "gfx")
+        };
+        let code = compact(&generate(&mut font).unwrap());
+        let (de, fr) = code.split_at(code.find("pubconstFONT_FR_FR:").unwrap());
+        assert!(de.contains("pubconstFONT_DE:"));
+        assert!(de.contains("start:65,") && de.contains("start:111,"));
+        assert!(!de.contains("start:86,"));
+        assert!(fr.contains("start:86,") && fr.contains("start:111,"));
+        assert!(!fr.contains("start:65,"));
+
+        // The font does not contain 'ß'.
+        fs::write(dir.join("de.json"), r#"{"a": "Aß"}"#).unwrap();
+        match generate(&mut font) {
+            Err(Error::MissingGlyphs(locale, missing)) => {
+                assert_eq!((locale.as_str(), missing), ("de", vec!['ß']));
+            }
+            _ => panic!("expected missing glyphs"),
+        }
+
+        // Both locales would be generated as FONT_DE_DE.
+        fs::write(dir.join("de.json"), r#"{"a": "A"}"#).unwrap();
+        fs::write(dir.join("de-DE.json"), "{}").unwrap();
+        fs::write(dir.join("de_DE.json"), "{}").unwrap();
+        let result = generate(&mut font);
+        fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(Error::LocaleCollision(first, second)) => {
+                assert_eq!((first.as_str(), second.as_str()), ("de-DE", "de_DE"));
+            }
+            _ => panic!("expected a locale collision"),
+        }
+    }
+
+    #[test]
+    fn test_empty_glyph() {
+        let glyphs = [(' ', [0, 0, 0, 0]), ('A', [0, 0, 600, 700])];
+        let mut font = load_font("empty-glyph", &test_font(&glyphs, &[]));
+        for type_ in [
+            FontType::RLE,
+            FontType::Bitmap,
+            FontType::NibbleRLE,
+            FontType::Auto,
+            FontType::Gray2,
+            FontType::Gray4,
+        ] {
+            let code = font
+                .generate_subset("FONT", 20, &Subset::from(" A"), type_, "gfx")
+                .unwrap();
+            // The monochrome rasterizer always sets a bitmap of at least one pixel.
+            if type_ == FontType::Gray2 || type_ == FontType::Gray4 {
+                assert!(compact(&code).contains("width:0,height:0,stride:0,"));
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Minimal reader for the tables of TrueType/OpenType files which are not exposed by FreeType.
+
+/// Returns the glyph index pairs listed in the horizontal format 0 subtables of the 'kern'
+/// table, which are the only pairs for which FreeType reports kerning.
+///
+/// Returns `None` if the file is not a single sfnt font or the table cannot be parsed, and an
+/// empty list if the font has no 'kern' table.
+pub fn kerning_pairs(data: &[u8]) -> Option<Vec<(u32, u32)>> {
+    let version = read_u32(data, 0)?;
+    if version != 0x0001_0000 && &data[..4] != b"true" && &data[..4] != b"OTTO" {
+        return None;
+    }
+    let num_tables = read_u16(data, 4)? as usize;
+    data.get(..12 + num_tables * 16)?;
+    let kern = (0..num_tables)
+        .map(|i| 12 + i * 16)
+        .find(|&record| data.get(record..record + 4) == Some(b"kern"));
+    let record = match kern {
+        Some(record) => record,
+        None => return Some(Vec::new()),
+    };
+    let offset = read_u32(data, record + 8)? as usize;
+    let length = read_u32(data, record + 12)? as usize;
+    let table = data.get(offset..offset + length)?;
+
+    // Only the Microsoft version of the table is supported by FreeType.
+    if read_u16(table, 0)? != 0 {
+        return Some(Vec::new());
+    }
+    let mut pairs = Vec::new();
+    let mut subtable = 4;
+    for _ in 0..read_u16(table, 2)? {
+        let length = read_u16(table, subtable + 2)? as usize;
+        let coverage = read_u16(table, subtable + 4)?;
+        // Horizontal kerning values in format 0, the override bit is ignored.
+        if coverage & !8 == 0x0001 {
+            let num_pairs = read_u16(table, subtable + 6)? as usize;
+            for i in 0..num_pairs {
+                let pair = subtable + 14 + i * 6;
+                pairs.push((
+                    read_u16(table, pair)? as u32,
+                    read_u16(table, pair + 2)? as u32,
+                ));
+            }
+        }
+        subtable += length;
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    Some(pairs)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Builds a TrueType font for tests, where each glyph is a filled rectangle.
+///
+/// `glyphs` lists the character and the rectangle (left, bottom, right, top) of each glyph in
+/// font units, with 1000 units per em. An empty rectangle results in an empty glyph. `kerning`
+/// lists the kerning between pairs of characters in font units.
+#[cfg(test)]
+pub fn test_font(glyphs: &[(char, [i16; 4])], kerning: &[(char, char, i16)]) -> Vec<u8> {
+    fn push_u16(data: &mut Vec<u8>, value: u16) {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    fn push_i16(data: &mut Vec<u8>, value: i16) {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+
+    // Glyph 0 is the empty .notdef glyph.
+    let num_glyphs = glyphs.len() as u16 + 1;
+    let glyph_index = |c: char| glyphs.iter().position(|g| g.0 == c).unwrap() as u16 + 1;
+
+    let mut head = Vec::new();
+    head.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    head.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    head.extend_from_slice(&0u32.to_be_bytes());
+    head.extend_from_slice(&0x5f0f_3cf5u32.to_be_bytes());
+    push_u16(&mut head, 0);
+    push_u16(&mut head, 1000);
+    head.extend_from_slice(&[0; 16]);
+    for value in [0, -200, 1000, 800] {
+        push_i16(&mut head, value);
+    }
+    // macStyle, lowestRecPPEM, fontDirectionHint, indexToLocFormat (short), glyphDataFormat.
+    for value in [0, 8, 2, 0, 0] {
+        push_i16(&mut head, value);
+    }
+
+    let mut hhea = Vec::new();
+    hhea.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    // Ascender, descender, line gap, maximum advance, minimum side bearings, maximum extent,
+    // caret slope and offset, reserved values and the metric data format.
+    for value in [800, -200, 0, 1000, 0, 0, 1000, 1, 0, 0, 0, 0, 0, 0, 0] {
+        push_i16(&mut hhea, value);
+    }
+    push_u16(&mut hhea, num_glyphs);
+
+    let mut maxp = Vec::new();
+    maxp.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    push_u16(&mut maxp, num_glyphs);
+    // Points and contours of simple and composite glyphs, zones and the limits of the
+    // (unused) instructions.
+    for value in [4, 1, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0] {
+        push_u16(&mut maxp, value);
+    }
+
+    let mut hmtx = Vec::new();
+    push_u16(&mut hmtx, 1000);
+    push_i16(&mut hmtx, 0);
+    for &(_, [left, _, right, _]) in glyphs {
+        push_u16(&mut hmtx, (right + 100) as u16);
+        push_i16(&mut hmtx, left);
+    }
+
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    push_u16(&mut loca, 0);
+    push_u16(&mut loca, 0);
+    for &(_, [left, bottom, right, top]) in glyphs {
+        if left < right && bottom < top {
+            push_i16(&mut glyf, 1);
+            for value in [left, bottom, right, top] {
+                push_i16(&mut glyf, value);
+            }
+            // Last point of the contour and the length of the instructions.
+            push_u16(&mut glyf, 3);
+            push_u16(&mut glyf, 0);
+            // Four points on the curve, with the coordinates stored as 16-bit deltas.
+            glyf.extend_from_slice(&[1; 4]);
+            for value in [left, 0, right - left, 0] {
+                push_i16(&mut glyf, value);
+            }
+            for value in [bottom, top - bottom, 0, bottom - top] {
+                push_i16(&mut glyf, value);
+            }
+        }
+        push_u16(&mut loca, (glyf.len() / 2) as u16);
+    }
+
+    // Format 4 character map with one segment per character.
+    let mut chars = glyphs.iter().map(|g| g.0 as u16).collect::<Vec<_>>();
+    chars.sort_unstable();
+    let seg_count = chars.len() as u16 + 1;
+    let search_range = 2 * (1 << (15 - seg_count.leading_zeros())) as u16;
+    let mut cmap = Vec::new();
+    for value in [0, 1, 3, 1] {
+        push_u16(&mut cmap, value);
+    }
+    cmap.extend_from_slice(&12u32.to_be_bytes());
+    push_u16(&mut cmap, 4);
+    push_u16(&mut cmap, 16 + seg_count * 8);
+    push_u16(&mut cmap, 0);
+    push_u16(&mut cmap, seg_count * 2);
+    push_u16(&mut cmap, search_range);
+    push_u16(&mut cmap, search_range.trailing_zeros() as u16 - 1);
+    push_u16(&mut cmap, seg_count * 2 - search_range);
+    chars.iter().for_each(|&c| push_u16(&mut cmap, c));
+    push_u16(&mut cmap, 0xffff);
+    push_u16(&mut cmap, 0);
+    chars.iter().for_each(|&c| push_u16(&mut cmap, c));
+    push_u16(&mut cmap, 0xffff);
+    for &c in chars.iter() {
+        push_u16(
+            &mut cmap,
+            glyph_index(char::from_u32(c as u32).unwrap()).wrapping_sub(c),
+        );
+    }
+    push_u16(&mut cmap, 1);
+    for _ in 0..seg_count {
+        push_u16(&mut cmap, 0);
+    }
+
+    let mut pairs = kerning
+        .iter()
+        .map(|&(left, right, value)| (glyph_index(left), glyph_index(right), value))
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+    let mut kern = Vec::new();
+    push_u16(&mut kern, 0);
+    push_u16(&mut kern, 1);
+    push_u16(&mut kern, 0);
+    push_u16(&mut kern, 14 + pairs.len() as u16 * 6);
+    push_u16(&mut kern, 0x0001);
+    // The binary search parameters are not used by FreeType.
+    for value in [pairs.len() as u16, 0, 0, 0] {
+        push_u16(&mut kern, value);
+    }
+    for (left, right, value) in pairs {
+        push_u16(&mut kern, left);
+        push_u16(&mut kern, right);
+        push_i16(&mut kern, value);
+    }
+
+    let mut tables = [
+        (b"cmap", cmap),
+        (b"glyf", glyf),
+        (b"head", head),
+        (b"hhea", hhea),
+        (b"hmtx", hmtx),
+        (b"kern", kern),
+        (b"loca", loca),
+        (b"maxp", maxp),
+    ];
+    let mut font = Vec::new();
+    font.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+    for value in [tables.len() as u16, 128, 3, 0] {
+        push_u16(&mut font, value);
+    }
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, table) in tables.iter_mut() {
+        font.extend_from_slice(&tag[..]);
+        font.extend_from_slice(&0u32.to_be_bytes());
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        table.resize(table.len().div_ceil(4) * 4, 0);
+        offset += table.len();
+    }
+    for (_, table) in tables.iter() {
+        font.extend_from_slice(table);
+    }
+    font
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kerning_pairs, test_font};
+
+    #[test]
+    fn test_kerning_pairs() {
+        let glyphs = [('A', [0, 0, 600, 700]), ('V', [0, 0, 600, 700])];
+        let data = test_font(&glyphs, &[('V', 'A', -50), ('A', 'V', -100)]);
+        assert_eq!(kerning_pairs(&data), Some(vec![(1, 2), (2, 1)]));
+        assert_eq!(kerning_pairs(&data[..8]), None);
+        assert_eq!(kerning_pairs(b"not a font"), None);
+    }
+}
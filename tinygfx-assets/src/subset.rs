@@ -0,0 +1,381 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use freetype::ffi;
+
+use super::{Error, Font};
+
+/// Set of characters which are included in a generated font.
+///
+/// Characters which are added multiple times are only included once.
+#[derive(Clone, Default, Debug)]
+pub struct Subset {
+    chars: BTreeSet<char>,
+}
+
+impl Subset {
+    pub fn new() -> Subset {
+        Subset {
+            chars: BTreeSet::new(),
+        }
+    }
+
+    /// Adds all characters of the string.
+    pub fn chars(mut self, chars: &str) -> Self {
+        self.chars.extend(chars.chars());
+        self
+    }
+
+    /// Adds a range of characters, e.g., `'a'..='z'`.
+    pub fn range(mut self, range: RangeInclusive<char>) -> Self {
+        self.chars.extend(range);
+        self
+    }
+
+    pub fn block(self, block: Block) -> Self {
+        self.range(block.range())
+    }
+
+    /// Adds all characters for which the font contains a glyph.
+    ///
+    /// The font is borrowed mutably, as FreeType stores the iteration state in the character
+    /// map of the face.
+    pub fn all(mut self, font: &mut Font) -> Self {
+        let face: ffi::FT_Face = font.face.raw_mut();
+        let mut glyph_index = 0;
+        let mut c = unsafe { ffi::FT_Get_First_Char(face, &mut glyph_index) };
+        while glyph_index != 0 {
+            if let Some(c) = char::from_u32(c as u32) {
+                self.chars.insert(c);
+            }
+            c = unsafe { ffi::FT_Get_Next_Char(face, c, &mut glyph_index) };
+        }
+        self
+    }
+
+    /// Adds all characters which occur in a UTF-8 text file, except for control characters
+    /// such as line breaks.
+    pub fn file(mut self, path: &str) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)?;
+        self.add_text(&text);
+        Ok(self)
+    }
+
+    /// Adds all characters which can be displayed by the messages of a translation catalog.
+    ///
+    /// The format is selected by the file extension: Fluent (`.ftl`), gettext (`.po`), or JSON
+    /// (`.json`, where all string values are used). Text which is inserted at runtime via
+    /// placeholders is not known and has to be added separately.
+    pub fn catalog(self, path: &str) -> Result<Self, Error> {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str());
+        match extension {
+            #[cfg(feature = "fluent")]
+            Some("ftl") => self.fluent(path),
+            #[cfg(feature = "gettext")]
+            Some("po") => self.gettext(path),
+            #[cfg(feature = "json")]
+            Some("json") => self.json(path),
+            _ => Err(Error::UnsupportedFormat),
+        }
+    }
+
+    /// Adds all characters of the messages and terms of a Fluent resource.
+    #[cfg(feature = "fluent")]
+    pub fn fluent(mut self, path: &str) -> Result<Self, Error> {
+        use fluent_syntax::ast::Entry;
+
+        let source = fs::read_to_string(path)?;
+        let resource = fluent_syntax::parser::parse(source.as_str())
+            .map_err(|(_, errors)| Error::Fluent(errors))?;
+        for entry in resource.body {
+            let (value, attributes) = match entry {
+                Entry::Message(message) => (message.value, message.attributes),
+                Entry::Term(term) => (Some(term.value), term.attributes),
+                _ => continue,
+            };
+            let patterns = value
+                .iter()
+                .chain(attributes.iter().map(|attribute| &attribute.value));
+            for pattern in patterns {
+                self.add_fluent_pattern(pattern);
+            }
+        }
+        Ok(self)
+    }
+
+    #[cfg(feature = "fluent")]
+    fn add_fluent_pattern(&mut self, pattern: &fluent_syntax::ast::Pattern<&str>) {
+        use fluent_syntax::ast::{Expression, PatternElement};
+
+        for element in pattern.elements.iter() {
+            match element {
+                PatternElement::TextElement { value } => self.add_text(value),
+                PatternElement::Placeable { expression } => match expression {
+                    Expression::Select { selector, variants } => {
+                        self.add_fluent_expression(selector);
+                        for variant in variants {
+                            self.add_fluent_pattern(&variant.value);
+                        }
+                    }
+                    Expression::Inline(expression) => self.add_fluent_expression(expression),
+                },
+            }
+        }
+    }
+
+    #[cfg(feature = "fluent")]
+    fn add_fluent_expression(&mut self, expression: &fluent_syntax::ast::InlineExpression<&str>) {
+        use fluent_syntax::ast::{Expression, InlineExpression};
+
+        match expression {
+            InlineExpression::StringLiteral { value } => self.add_text(value),
+            InlineExpression::Placeable { expression } => match &**expression {
+                Expression::Select { selector, variants } => {
+                    self.add_fluent_expression(selector);
+                    for variant in variants {
+                        self.add_fluent_pattern(&variant.value);
+                    }
+                }
+                Expression::Inline(expression) => self.add_fluent_expression(expression),
+            },
+            // References are resolved when the referenced messages are harvested.
+            _ => {}
+        }
+    }
+
+    /// Adds all characters of the translations of a gettext catalog. For untranslated
+    /// messages, the message ID is used.
+    #[cfg(feature = "gettext")]
+    pub fn gettext(mut self, path: &str) -> Result<Self, Error> {
+        let catalog = polib::po_file::parse(Path::new(path))?;
+        for message in catalog.messages() {
+            if !message.is_translated() {
+                self.add_text(message.msgid());
+                if let Ok(plural) = message.msgid_plural() {
+                    self.add_text(plural);
+                }
+            } else if let Ok(translations) = message.msgstr_plural() {
+                for translation in translations {
+                    self.add_text(translation);
+                }
+            } else if let Ok(translation) = message.msgstr() {
+                self.add_text(translation);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Adds all characters of the string values in a JSON file. Object keys are ignored.
+    #[cfg(feature = "json")]
+    pub fn json(mut self, path: &str) -> Result<Self, Error> {
+        let text = fs::read_to_string(path)?;
+        let value: serde_json::Value = serde_json::from_str(&text)?;
+        self.add_json_value(&value);
+        Ok(self)
+    }
+
+    #[cfg(feature = "json")]
+    fn add_json_value(&mut self, value: &serde_json::Value) {
+        use serde_json::Value;
+
+        match value {
+            Value::String(text) => self.add_text(text),
+            Value::Array(values) => values.iter().for_each(|value| self.add_json_value(value)),
+            Value::Object(map) => map.values().for_each(|value| self.add_json_value(value)),
+            _ => {}
+        }
+    }
+
+    /// Harvests the catalogs of all locales in a directory.
+    ///
+    /// Each subdirectory is a locale and all catalogs within are combined (e.g.,
+    /// `locales/de-DE/main.ftl`), and each catalog file directly in the directory is a locale
+    /// named after the file (e.g., `po/de.po`). The result is sorted by locale.
+    pub fn locales(dir: &str) -> Result<Vec<(String, Subset)>, Error> {
+        let mut locales = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let subset = if path.is_dir() {
+                let mut subset = Subset::new();
+                for entry in fs::read_dir(&path)? {
+                    let path = entry?.path();
+                    if path.is_file() && is_catalog(&path) {
+                        subset = subset.catalog(path.to_str().unwrap())?;
+                    }
+                }
+                subset
+            } else if is_catalog(&path) {
+                Subset::new().catalog(path.to_str().unwrap())?
+            } else {
+                continue;
+            };
+            let locale = path.file_stem().unwrap().to_string_lossy().into_owned();
+            locales.push((locale, subset));
+        }
+        locales.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(locales)
+    }
+
+    /// Adds all characters of another subset.
+    pub fn union(mut self, other: &Subset) -> Self {
+        self.chars.extend(other.iter());
+        self
+    }
+
+    fn add_text(&mut self, text: &str) {
+        self.chars.extend(text.chars().filter(|c| !c.is_control()));
+    }
+
+    /// Returns the characters in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = char> + '_ {
+        self.chars.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+}
+
+impl<'a> From<&'a str> for Subset {
+    fn from(chars: &'a str) -> Subset {
+        Subset::new().chars(chars)
+    }
+}
+
+/// File extensions of the supported translation catalog formats.
+const CATALOG_EXTENSIONS: &[&str] = &[
+    #[cfg(feature = "fluent")]
+    "ftl",
+    #[cfg(feature = "gettext")]
+    "po",
+    #[cfg(feature = "json")]
+    "json",
+];
+
+fn is_catalog(path: &Path) -> bool {
+    let extension = path.extension().and_then(|e| e.to_str());
+    extension.is_some_and(|e| CATALOG_EXTENSIONS.contains(&e))
+}
+
+/// Named ranges of Unicode characters.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Block {
+    /// Printable ASCII characters (U+0020 to U+007E).
+    BasicLatin,
+    /// Printable characters of the Latin-1 Supplement block (U+00A0 to U+00FF).
+    Latin1Supplement,
+    /// Greek and Coptic (U+0370 to U+03FF).
+    Greek,
+    /// Cyrillic (U+0400 to U+04FF).
+    Cyrillic,
+}
+
+impl Block {
+    pub fn range(&self) -> RangeInclusive<char> {
+        match self {
+            Block::BasicLatin => '\u{20}'..='\u{7e}',
+            Block::Latin1Supplement => '\u{a0}'..='\u{ff}',
+            Block::Greek => '\u{370}'..='\u{3ff}',
+            Block::Cyrillic => '\u{400}'..='\u{4ff}',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use super::super::Font;
+    use super::{Block, Subset};
+
+    /// Returns a temporary path which is unique to the test, so that tests can run in parallel.
+    fn temp_path(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("tinygfx-assets-{}-{}", test, process::id()))
+    }
+
+    #[test]
+    fn test_subset() {
+        let subset = Subset::new()
+            .chars("cab")
+            .range('a'..='e')
+            .block(Block::BasicLatin);
+        assert_eq!(subset.len(), 95);
+        assert_eq!(subset.iter().next(), Some(' '));
+        assert_eq!(subset.iter().last(), Some('~'));
+
+        let path = temp_path("subset");
+        fs::write(&path, "Привет\nмир\n").unwrap();
+        let subset = Subset::new().file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(subset.iter().collect::<String>(), "Пвеимрт");
+    }
+
+    #[test]
+    #[cfg(all(feature = "fluent", feature = "gettext", feature = "json"))]
+    fn test_catalogs() {
+        let dir = temp_path("catalogs");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("de")).unwrap();
+        fs::write(
+            dir.join("de/main.ftl"),
+            "hello = Grüße, { $name }!\n\
+             items = { $count ->\n    [one] ein Ä\n   *[other] { $count } Ö\n}\n\
+             -brand = ß\n    .title = ü\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("fr.po"),
+            "msgid \"\"\nmsgstr \"\"\n\
+             \"Project-Id-Version: test\\n\"\n\"POT-Creation-Date: \\n\"\n\
+             \"PO-Revision-Date: \\n\"\n\"Language-Team: \\n\"\n\"Language: fr\\n\"\n\
+             \"MIME-Version: 1.0\\n\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+             \"Content-Transfer-Encoding: 8bit\\n\"\n\"Plural-Forms: nplurals=2; plural=(n > 1);\\n\"\n\n\
+             msgid \"Hello\"\nmsgstr \"Salut é\"\n\n\
+             msgid \"Untranslated\"\nmsgstr \"\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("ru.json"),
+            r#"{"hello": "Привет", "list": ["мир", 1, {"key": "ё"}]}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("README.txt"), "not a catalog").unwrap();
+
+        let locales = Subset::locales(dir.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let locales = locales
+            .iter()
+            .map(|(locale, subset)| (locale.as_str(), subset.iter().collect::<String>()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            locales,
+            [
+                ("de", String::from(" !,GeinrÄÖßü")),
+                ("fr", String::from(" SUadelnrstué")),
+                ("ru", String::from("Пвеимртё")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_glyphs() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../tinygfx/assets/Roboto-Regular.ttf"
+        );
+        let mut font = Font::load(path).unwrap();
+        let subset = Subset::new().all(&mut font);
+        assert!(subset.len() > 500);
+        assert!(subset.iter().any(|c| c == 'Ж'));
+        assert!(!subset.iter().any(|c| c == '\u{4e00}'));
+    }
+}
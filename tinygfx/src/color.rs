@@ -1,13 +1,35 @@
+use core::cmp::min;
 use core::convert::TryInto;
 
 pub trait Color: Copy + Clone {
     fn bits_per_pixel() -> usize;
     fn fill(&self, buffer: &mut [u8], left: i32, right: i32);
+    /// Draws the set pixels of `bitmap[left..right]` (MSB-first) at `x + left..x + right`.
     fn render_bitmap_row(&self, buffer: &mut [u8], x: i32, bitmap: &[u8], left: i32, right: i32);
     fn mirror_x(buffer: &mut [u8], width: usize);
+    /// Sets the pixels selected by `mask` in every byte of a page-addressed buffer, where each
+    /// byte contains a column of eight pixels.
+    fn fill_page(&self, buffer: &mut [u8], mask: u8);
+    /// Returns the color of the pixel in row `row` (0 to 7) of a page-addressed byte.
+    fn get_page(byte: u8, row: usize) -> Self;
+    /// Returns the color of the pixel at `x` within a row.
+    fn get(buffer: &[u8], x: i32) -> Self;
+    /// Sets the color of the pixel at `x` within a row.
+    fn set(&self, buffer: &mut [u8], x: i32);
+    /// Mixes this color with `background`, where `alpha` (0 to 255) is the weight of this
+    /// color.
+    ///
+    /// The default implementation selects the color with the larger weight.
+    fn mix(&self, background: Self, alpha: u8) -> Self {
+        if alpha >= 128 {
+            *self
+        } else {
+            background
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug)]
 pub enum BlackWhite {
     White,
     Black,
@@ -36,16 +58,16 @@ impl Color for BlackWhite {
             // We cross byte boundaries.
             if let Self::White = self {
                 buffer[left_index] |= left_mask;
-                for i in (left_index + 1)..right_index {
-                    buffer[i] = 0xff;
+                for byte in &mut buffer[(left_index + 1)..right_index] {
+                    *byte = 0xff;
                 }
                 if right_offset != 0 {
                     buffer[right_index] |= right_mask;
                 }
             } else {
                 buffer[left_index] &= !left_mask;
-                for i in (left_index + 1)..right_index {
-                    buffer[i] = 0x0;
+                for byte in &mut buffer[(left_index + 1)..right_index] {
+                    *byte = 0x0;
                 }
                 if right_offset != 0 {
                     buffer[right_index] &= !right_mask;
@@ -54,69 +76,33 @@ impl Color for BlackWhite {
         }
     }
 
-    fn render_bitmap_row(
-        &self,
-        buffer: &mut [u8],
-        x: i32,
-        bitmap: &[u8],
-        mut left: i32,
-        right: i32,
-    ) {
-        //let left_mask = 0xff >> (left & !7);
-        //let right_mask = (0xff00u16 >> (right & !7)) as u8;
-
-        if (left & !7) == (right & !7) {
-            // Only one byte to copy.
-            self.render_bitmap_row_naive(buffer, x, bitmap, left, right);
-            // TODO: Probably, the naive method is cheaper!
-            /*let mask = left_mask & right_mask;
-            let value = bitmap[left as usize & !7] & mask;
-            let left_mask = 0xff >> output_shift;
-            let left_value = value >> output_shift;
-            let right_mask = 0xff << (8 - output_shift);
-            let right_value = value << (8 - output_shift);
-            buffer[first_byte] = (buffer[first_byte] & left_mask) | left_value;
-            if first_byte != buffer.len() {
-                buffer[first_byte + 1] = (buffer[first_byte + 1] & right_mask) | right_value;
-            }*/
-            return;
-        }
-
-        let output_shift = x & 0x7;
-        if left & 7 != 0 {
-            // Write the first (partial) byte.
-            let next_left = (left + 7) & !7;
-            self.render_bitmap_row_naive(buffer, x, bitmap, left, next_left);
-            left = next_left;
-        }
-
-        if output_shift != 0 {
-            while left + 8 <= right {
-                let index = (x + left) as usize & !7;
-                let value = bitmap[left as usize >> 3];
-                let left_mask = 0xff >> output_shift;
-                let left_value = value >> output_shift;
-                let right_mask = 0xff << (8 - output_shift);
-                let right_value = value << (8 - output_shift);
-                buffer[index] = (buffer[index] & left_mask) | left_value;
-                buffer[index + 1] = (buffer[index + 1] & right_mask) | right_value;
-                left += 8;
+    fn render_bitmap_row(&self, buffer: &mut [u8], x: i32, bitmap: &[u8], left: i32, right: i32) {
+        // The bitmap is stored MSB-first, and only the set pixels are drawn.
+        let mut left = left;
+        while left < right {
+            let byte_start = left & !7;
+            let byte_end = min(byte_start + 8, right);
+            let left_mask = 0xffu8 >> (left - byte_start);
+            let right_mask = (0xff00u16 >> (byte_end - byte_start)) as u8;
+            let bits = bitmap[(byte_start >> 3) as usize] & left_mask & right_mask;
+            // The output is not necessarily aligned, so the bits can span two bytes. If `x` is
+            // negative, the first byte can be left of the buffer, but then all its bits are
+            // clipped.
+            let output = x + byte_start;
+            let index = output >> 3;
+            let bits = ((bits as u16) << 8) >> (output & 7);
+            for (i, bits) in [(bits >> 8) as u8, bits as u8].iter().enumerate() {
+                if *bits == 0 {
+                    continue;
+                }
+                let index = (index + i as i32) as usize;
+                if let Self::White = self {
+                    buffer[index] |= bits;
+                } else {
+                    buffer[index] &= !bits;
+                }
             }
-        } else {
-            // Aligned => direct copy.
-            let next_left = right & !7;
-            let first_output_index = (x + left) as usize >> 3;
-            let last_output_index = (x + next_left) as usize >> 3;
-            let first_input_index = left as usize >> 3;
-            let last_input_index = next_left as usize >> 3;
-            buffer[first_output_index..last_output_index]
-                .copy_from_slice(&bitmap[first_input_index..last_input_index]);
-            left = next_left;
-        }
-
-        if left != right {
-            // Write the last (partial) byte.
-            self.render_bitmap_row_naive(buffer, x, bitmap, left, right);
+            left = byte_end;
         }
     }
 
@@ -169,51 +155,269 @@ impl Color for BlackWhite {
             buffer[bytes - 1] <<= shift;
         }
     }
-}
 
-impl BlackWhite {
-    fn render_bitmap_row_naive(
-        &self,
-        buffer: &mut [u8],
-        x: i32,
-        bitmap: &[u8],
-        left: i32,
-        right: i32,
-    ) {
-        for x in left + x..right + x {
-            let byte = (x - left) >> 3;
-            let bit = (x - left) & 7;
-            if (bitmap[byte as usize] & (1 << bit)) != 0 {
-                buffer[(x / 8) as usize] |= 0x80 >> (x & 7);
-            } else {
-                buffer[(x / 8) as usize] &= !(0x80 >> (x & 7));
+    fn fill_page(&self, buffer: &mut [u8], mask: u8) {
+        if let Self::White = self {
+            for byte in buffer {
+                *byte |= mask;
+            }
+        } else {
+            for byte in buffer {
+                *byte &= !mask;
             }
         }
     }
+
+    fn get_page(byte: u8, row: usize) -> Self {
+        if byte & (1 << row) != 0 {
+            Self::White
+        } else {
+            Self::Black
+        }
+    }
+
+    fn get(buffer: &[u8], x: i32) -> Self {
+        if buffer[(x >> 3) as usize] & (0x80 >> (x & 7)) != 0 {
+            Self::White
+        } else {
+            Self::Black
+        }
+    }
+
+    fn set(&self, buffer: &mut [u8], x: i32) {
+        let mask = 0x80 >> (x & 7);
+        if let Self::White = self {
+            buffer[(x >> 3) as usize] |= mask;
+        } else {
+            buffer[(x >> 3) as usize] &= !mask;
+        }
+    }
+}
+
+/// Gray level with 2 bits per pixel, from 0 (black) to 3 (white).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Gray2(pub u8);
+
+/// Gray level with 4 bits per pixel, from 0 (black) to 15 (white).
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Gray4(pub u8);
+
+impl Color for Gray2 {
+    fn bits_per_pixel() -> usize {
+        2
+    }
+
+    fn fill(&self, buffer: &mut [u8], left: i32, right: i32) {
+        fill_packed(buffer, left, right, 2, self.0);
+    }
+
+    fn render_bitmap_row(&self, buffer: &mut [u8], x: i32, bitmap: &[u8], left: i32, right: i32) {
+        render_bitmap_row_packed(buffer, x, bitmap, left, right, 2, self.0);
+    }
+
+    fn mirror_x(buffer: &mut [u8], width: usize) {
+        mirror_x_packed(buffer, width, 2);
+    }
+
+    fn fill_page(&self, _buffer: &mut [u8], _mask: u8) {
+        unreachable!("Layout::Pages requires 1 bit per pixel");
+    }
+
+    fn get_page(_byte: u8, _row: usize) -> Self {
+        unreachable!("Layout::Pages requires 1 bit per pixel");
+    }
+
+    fn get(buffer: &[u8], x: i32) -> Self {
+        Self(get_packed(buffer, x, 2))
+    }
+
+    fn set(&self, buffer: &mut [u8], x: i32) {
+        set_packed(buffer, x, 2, self.0);
+    }
+
+    fn mix(&self, background: Self, alpha: u8) -> Self {
+        Self(mix_levels(self.0, background.0, alpha))
+    }
+}
+
+impl Color for Gray4 {
+    fn bits_per_pixel() -> usize {
+        4
+    }
+
+    fn fill(&self, buffer: &mut [u8], left: i32, right: i32) {
+        fill_packed(buffer, left, right, 4, self.0);
+    }
+
+    fn render_bitmap_row(&self, buffer: &mut [u8], x: i32, bitmap: &[u8], left: i32, right: i32) {
+        render_bitmap_row_packed(buffer, x, bitmap, left, right, 4, self.0);
+    }
+
+    fn mirror_x(buffer: &mut [u8], width: usize) {
+        mirror_x_packed(buffer, width, 4);
+    }
+
+    fn fill_page(&self, _buffer: &mut [u8], _mask: u8) {
+        unreachable!("Layout::Pages requires 1 bit per pixel");
+    }
+
+    fn get_page(_byte: u8, _row: usize) -> Self {
+        unreachable!("Layout::Pages requires 1 bit per pixel");
+    }
+
+    fn get(buffer: &[u8], x: i32) -> Self {
+        Self(get_packed(buffer, x, 4))
+    }
+
+    fn set(&self, buffer: &mut [u8], x: i32) {
+        set_packed(buffer, x, 4, self.0);
+    }
+
+    fn mix(&self, background: Self, alpha: u8) -> Self {
+        Self(mix_levels(self.0, background.0, alpha))
+    }
+}
+
+// Helpers for colors with multiple pixels per byte, where the first pixel is stored in the
+// most significant bits.
+
+fn get_packed(buffer: &[u8], x: i32, bits_per_pixel: usize) -> u8 {
+    let bit = x as usize * bits_per_pixel;
+    let mask = (1u8 << bits_per_pixel) - 1;
+    (buffer[bit >> 3] >> (8 - bits_per_pixel - (bit & 7))) & mask
+}
+
+fn set_packed(buffer: &mut [u8], x: i32, bits_per_pixel: usize, value: u8) {
+    let bit = x as usize * bits_per_pixel;
+    let shift = 8 - bits_per_pixel - (bit & 7);
+    let mask = ((1u8 << bits_per_pixel) - 1) << shift;
+    let byte = &mut buffer[bit >> 3];
+    *byte = (*byte & !mask) | ((value << shift) & mask);
+}
+
+fn fill_packed(buffer: &mut [u8], left: i32, right: i32, bits_per_pixel: usize, value: u8) {
+    let pixels_per_byte = (8 / bits_per_pixel) as i32;
+    // Out-of-range levels must not spill into the neighbouring pixels of the repeated byte.
+    let value = value & ((1u8 << bits_per_pixel) - 1);
+    let mut x = left;
+    // Set single pixels until the next byte boundary, then complete bytes.
+    while x < right && x % pixels_per_byte != 0 {
+        set_packed(buffer, x, bits_per_pixel, value);
+        x += 1;
+    }
+    let mut byte_value = 0;
+    for _ in 0..pixels_per_byte {
+        byte_value = (byte_value << bits_per_pixel) | value;
+    }
+    while x + pixels_per_byte <= right {
+        buffer[(x / pixels_per_byte) as usize] = byte_value;
+        x += pixels_per_byte;
+    }
+    while x < right {
+        set_packed(buffer, x, bits_per_pixel, value);
+        x += 1;
+    }
+}
+
+fn render_bitmap_row_packed(
+    buffer: &mut [u8],
+    x: i32,
+    bitmap: &[u8],
+    left: i32,
+    right: i32,
+    bits_per_pixel: usize,
+    value: u8,
+) {
+    for i in left..right {
+        if bitmap[(i >> 3) as usize] & (0x80 >> (i & 7)) != 0 {
+            set_packed(buffer, x + i, bits_per_pixel, value);
+        }
+    }
+}
+
+fn mirror_x_packed(buffer: &mut [u8], width: usize, bits_per_pixel: usize) {
+    for x in 0..width / 2 {
+        let left = get_packed(buffer, x as i32, bits_per_pixel);
+        let right = get_packed(buffer, (width - 1 - x) as i32, bits_per_pixel);
+        set_packed(buffer, x as i32, bits_per_pixel, right);
+        set_packed(buffer, (width - 1 - x) as i32, bits_per_pixel, left);
+    }
+}
+
+fn mix_levels(foreground: u8, background: u8, alpha: u8) -> u8 {
+    let alpha = alpha as u32;
+    ((foreground as u32 * alpha + background as u32 * (255 - alpha) + 127) / 255) as u8
 }
 
 #[cfg(test)]
 mod tests {
     use super::BlackWhite::{self, Black, White};
-    use super::Color;
+    use super::{Color, Gray2, Gray4};
 
     #[test]
     fn bitmap_test() {
         // (color, bitmap, x, left, right, expected)
         let tests = [
-            (White, 3, &[0], 3, 4, &[0; 4]),
+            (White, 3, &[0][..], 3, 4, &[0; 4]),
             (White, 3, &[0xff], 3, 4, &[0x02, 0x00, 0x00, 0x00]),
             (White, 3, &[0xff], 0, 8, &[0x1f, 0xe0, 0x00, 0x00]),
             (White, 0, &[0xff], 0, 8, &[0xff, 0x00, 0x00, 0x00]),
             (White, 0, &[0xff], 3, 7, &[0x1e, 0x00, 0x00, 0x00]),
+            (White, 5, &[0x81, 0x80], 0, 9, &[0x04, 0x0c, 0x00, 0x00]),
+            (Black, 0, &[0xf0], 2, 8, &[0xcf, 0xff, 0xff, 0xff]),
+            // Clipped at the left edge of the buffer.
+            (White, -3, &[0xff, 0xff], 3, 16, &[0xff, 0xf8, 0x00, 0x00]),
+            (White, -11, &[0xff, 0xff], 11, 16, &[0xf8, 0x00, 0x00, 0x00]),
+            (Black, -3, &[0xff, 0xff], 3, 16, &[0x00, 0x07, 0xff, 0xff]),
         ];
         for test in tests.iter() {
-            let mut output = [0; 4];
+            let mut output = if test.0 == Black { [0xff; 4] } else { [0; 4] };
             test.0
                 .render_bitmap_row(&mut output, test.1, test.2, test.3, test.4);
             assert_eq!(&output, test.5);
         }
-        // TODO
+    }
+
+    #[test]
+    fn get_set_test() {
+        let mut buffer = [0x0f, 0x00];
+        assert_eq!(BlackWhite::get(&buffer, 3), Black);
+        assert_eq!(BlackWhite::get(&buffer, 4), White);
+        White.set(&mut buffer, 9);
+        Black.set(&mut buffer, 7);
+        assert_eq!(buffer, [0x0e, 0x40]);
+        assert_eq!(BlackWhite::get_page(0x04, 2), White);
+        assert_eq!(BlackWhite::get_page(0xfb, 2), Black);
+    }
+
+    #[test]
+    fn gray_test() {
+        let mut buffer = [0u8; 3];
+        Gray2(2).fill(&mut buffer, 3, 9);
+        assert_eq!(buffer, [0x02, 0xaa, 0x80]);
+        assert_eq!(Gray2::get(&buffer, 8), Gray2(2));
+        assert_eq!(Gray2::get(&buffer, 9), Gray2(0));
+        Gray2(1).set(&mut buffer, 9);
+        assert_eq!(buffer, [0x02, 0xaa, 0x90]);
+        Gray2::mirror_x(&mut buffer, 10);
+        assert_eq!(buffer, [0x6a, 0xa8, 0x00]);
+
+        // Levels are limited to the number of bits per pixel.
+        let mut buffer = [0u8; 2];
+        Gray2(5).fill(&mut buffer, 0, 8);
+        assert_eq!(buffer, [0x55, 0x55]);
+
+        let mut buffer = [0u8; 2];
+        Gray4(0xc).render_bitmap_row(&mut buffer, 1, &[0xa0], 0, 3);
+        assert_eq!(buffer, [0x0c, 0x0c]);
+        Gray4(0xf).fill(&mut buffer, 0, 4);
+        assert_eq!(buffer, [0xff, 0xff]);
+
+        assert_eq!(Gray4(15).mix(Gray4(0), 255), Gray4(15));
+        assert_eq!(Gray4(15).mix(Gray4(3), 0), Gray4(3));
+        assert_eq!(Gray4(15).mix(Gray4(5), 128), Gray4(10));
+        assert_eq!(White.mix(Black, 200), White);
+        assert_eq!(White.mix(Black, 100), Black);
     }
 }
 
@@ -0,0 +1,151 @@
+use core::cmp::{max, min};
+use core::ops::Range;
+
+/// Consecutive rows which differ between two buffers.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Change {
+    /// Changed rows. For `Layout::Pages`, the range always covers complete pages.
+    pub rows: Range<u32>,
+    /// Bytes within each row (or page) which contain all changes of the rows.
+    pub bytes: Range<usize>,
+}
+
+/// Iterator over the differences between two buffers with the same geometry.
+///
+/// Use `Frame::diff()` to compare buffers produced by `Frame::draw_part()`.
+pub struct Diff<'a> {
+    old: &'a [u8],
+    new: &'a [u8],
+    stride: usize,
+    row_bytes: usize,
+    rows_per_stride: u32,
+    first_row: u32,
+    index: usize,
+}
+
+impl<'a> Diff<'a> {
+    /// Compares two buffers with `stride` bytes per row.
+    ///
+    /// Panics if `stride` is 0.
+    pub fn new(old: &'a [u8], new: &'a [u8], stride: usize) -> Self {
+        assert!(stride > 0);
+        Self {
+            old,
+            new,
+            stride,
+            row_bytes: stride,
+            rows_per_stride: 1,
+            first_row: 0,
+            index: 0,
+        }
+    }
+
+    /// Ignores the padding at the end of each row, only the first `row_bytes` bytes of each
+    /// row are compared.
+    pub fn row_bytes(mut self, row_bytes: usize) -> Self {
+        self.row_bytes = row_bytes;
+        self
+    }
+
+    /// Sets the number of rows contained in each stride (8 for `Layout::Pages`).
+    pub fn rows_per_stride(mut self, rows_per_stride: u32) -> Self {
+        self.rows_per_stride = rows_per_stride;
+        self
+    }
+
+    /// Sets the number of the first row in the buffers, so that the reported rows match the
+    /// `y` passed to `Frame::draw_part()`.
+    pub fn first_row(mut self, first_row: u32) -> Self {
+        self.first_row = first_row;
+        self
+    }
+
+    fn changed_bytes(&self, index: usize) -> Option<Range<usize>> {
+        let start = index * self.stride;
+        let old = &self.old[start..start + self.row_bytes];
+        let new = &self.new[start..start + self.row_bytes];
+        let first = old.iter().zip(new).position(|(a, b)| a != b)?;
+        let last = old.iter().zip(new).rposition(|(a, b)| a != b).unwrap();
+        Some(first..last + 1)
+    }
+
+    fn row(&self, index: usize) -> u32 {
+        self.first_row + index as u32 * self.rows_per_stride
+    }
+}
+
+impl<'a> Iterator for Diff<'a> {
+    type Item = Change;
+
+    fn next(&mut self) -> Option<Change> {
+        let strides = min(self.old.len(), self.new.len()) / self.stride;
+        // Skip unchanged rows.
+        let mut bytes = loop {
+            if self.index >= strides {
+                return None;
+            }
+            self.index += 1;
+            if let Some(bytes) = self.changed_bytes(self.index - 1) {
+                break bytes;
+            }
+        };
+        let start = self.index - 1;
+        // Merge all directly following changed rows.
+        while self.index < strides {
+            match self.changed_bytes(self.index) {
+                Some(row_bytes) => {
+                    bytes = min(bytes.start, row_bytes.start)..max(bytes.end, row_bytes.end);
+                    self.index += 1;
+                }
+                None => break,
+            }
+        }
+        Some(Change {
+            rows: self.row(start)..self.row(self.index),
+            bytes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Diff};
+
+    #[test]
+    fn test_diff() {
+        let old = [0u8; 12];
+        let new = [0, 0, 0, 0, 1, 0, 0, 0, 0, 2, 2, 0];
+        let changes = [
+            Change {
+                rows: 2..3,
+                bytes: 0..1,
+            },
+            Change {
+                rows: 4..6,
+                bytes: 0..2,
+            },
+        ];
+        let mut diff = Diff::new(&old, &new, 2);
+        assert_eq!(diff.next(), Some(changes[0].clone()));
+        assert_eq!(diff.next(), Some(changes[1].clone()));
+        assert_eq!(diff.next(), None);
+
+        // Padding is ignored.
+        let new = [0, 0, 5, 0, 0, 0, 0, 1, 7, 0, 0, 0];
+        let mut diff = Diff::new(&old, &new, 3).row_bytes(2).first_row(8);
+        assert_eq!(
+            diff.next(),
+            Some(Change {
+                rows: 10..11,
+                bytes: 1..2,
+            })
+        );
+        assert_eq!(diff.next(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_stride() {
+        Diff::new(&[], &[], 0);
+    }
+}
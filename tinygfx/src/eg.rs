@@ -0,0 +1,477 @@
+//! Interoperability with `embedded-graphics`.
+//!
+//! `Renderer` implements `DrawTarget`, so that embedded-graphics drawables can be rendered into
+//! frames. In the other direction, the images implement `ImageDrawable` and fonts can be used
+//! to draw text via `FontStyle`.
+
+use core::convert::Infallible;
+
+use embedded_graphics::image::ImageDrawable;
+use embedded_graphics::pixelcolor::raw::{RawU1, RawU2, RawU4};
+use embedded_graphics::pixelcolor::{self, BinaryColor, GrayColor, PixelColor};
+use embedded_graphics::prelude::{DrawTarget, OriginDimensions, Pixel, Point, Size};
+use embedded_graphics::primitives::{PointsIter, Rectangle};
+use embedded_graphics::text::renderer::{TextMetrics, TextRenderer};
+use embedded_graphics::text::Baseline;
+
+use super::color::{BlackWhite, Color, Gray2, Gray4};
+use super::font::Font;
+use super::image::{MonoBitmapImage, MonoImageData, MonoRLEImage};
+use super::Renderer;
+
+impl PixelColor for BlackWhite {
+    type Raw = RawU1;
+}
+
+impl From<BinaryColor> for BlackWhite {
+    fn from(color: BinaryColor) -> Self {
+        match color {
+            BinaryColor::Off => BlackWhite::Black,
+            BinaryColor::On => BlackWhite::White,
+        }
+    }
+}
+
+impl From<BlackWhite> for BinaryColor {
+    fn from(color: BlackWhite) -> Self {
+        match color {
+            BlackWhite::Black => BinaryColor::Off,
+            BlackWhite::White => BinaryColor::On,
+        }
+    }
+}
+
+impl PixelColor for Gray2 {
+    type Raw = RawU2;
+}
+
+impl From<pixelcolor::Gray2> for Gray2 {
+    fn from(color: pixelcolor::Gray2) -> Self {
+        Gray2(color.luma())
+    }
+}
+
+impl From<Gray2> for pixelcolor::Gray2 {
+    fn from(color: Gray2) -> Self {
+        pixelcolor::Gray2::new(color.0)
+    }
+}
+
+impl PixelColor for Gray4 {
+    type Raw = RawU4;
+}
+
+impl From<pixelcolor::Gray4> for Gray4 {
+    fn from(color: pixelcolor::Gray4) -> Self {
+        Gray4(color.luma())
+    }
+}
+
+impl From<Gray4> for pixelcolor::Gray4 {
+    fn from(color: Gray4) -> Self {
+        pixelcolor::Gray4::new(color.0)
+    }
+}
+
+impl<'a, ColorType> OriginDimensions for Renderer<'a, ColorType> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+/// Allows drawing embedded-graphics primitives, text and images into the part of the frame
+/// covered by the renderer. Everything outside of the part is discarded.
+impl<'a, ColorType> DrawTarget for Renderer<'a, ColorType>
+where
+    ColorType: Color + PixelColor,
+{
+    type Color = ColorType;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let clip = self.full_frame();
+        for Pixel(point, color) in pixels {
+            self.set_pixel(clip, point.x, point.y, color);
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let clip = self.full_frame();
+        let width = area.size.width as usize;
+        let mut colors = colors.into_iter();
+        for y in area.rows() {
+            if !clip.contains_row(y) {
+                // Skip the colors of the row.
+                if width != 0 && colors.nth(width - 1).is_none() {
+                    break;
+                }
+                continue;
+            }
+            // Draw runs of pixels with the same color as spans.
+            let mut run: Option<(i32, ColorType)> = None;
+            // End of the pixels for which colors were provided.
+            let mut end = area.columns().start;
+            for x in area.columns() {
+                let color = match colors.next() {
+                    Some(color) => color,
+                    None => break,
+                };
+                end = x + 1;
+                match run {
+                    Some((_, run_color)) if run_color == color => {}
+                    Some((start, run_color)) => {
+                        self.fill(clip, start, y, x, y + 1, run_color);
+                        run = Some((x, color));
+                    }
+                    None => run = Some((x, color)),
+                }
+            }
+            if let Some((start, run_color)) = run {
+                self.fill(clip, start, y, end, y + 1, run_color);
+            }
+            if end != area.columns().end {
+                // The colors ended within the row.
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let clip = self.full_frame();
+        let bottom_right = area.top_left + area.size;
+        self.fill(
+            clip,
+            area.top_left.x,
+            area.top_left.y,
+            bottom_right.x,
+            bottom_right.y,
+            color,
+        );
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        Renderer::clear(self, color);
+        Ok(())
+    }
+}
+
+fn draw_mono_image<Image, D>(
+    image: &Image,
+    target: &mut D,
+    area: &Rectangle,
+) -> Result<(), D::Error>
+where
+    Image: MonoImageData,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let colors = area
+        .points()
+        .map(|p| BinaryColor::from(image.get_pixel(p.x as u32, p.y as u32)));
+    target.fill_contiguous(&Rectangle::new(Point::zero(), area.size), colors)
+}
+
+impl OriginDimensions for MonoBitmapImage {
+    fn size(&self) -> Size {
+        Size::new(self.width(), self.height())
+    }
+}
+
+impl ImageDrawable for MonoBitmapImage {
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_mono_image(self, target, &Rectangle::new(Point::zero(), self.size()))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_mono_image(self, target, area)
+    }
+}
+
+impl OriginDimensions for MonoRLEImage {
+    fn size(&self) -> Size {
+        Size::new(self.width(), self.height())
+    }
+}
+
+impl ImageDrawable for MonoRLEImage {
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_mono_image(self, target, &Rectangle::new(Point::zero(), self.size()))
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        draw_mono_image(self, target, area)
+    }
+}
+
+/// Text style which draws text with a tinygfx `Font`, for use with
+/// `embedded_graphics::text::Text`.
+///
+/// Only the set pixels of the glyphs are drawn, the background is left unchanged.
+///
+/// Like the fonts of embedded-graphics, `Baseline::Bottom` places the last row of the descender
+/// at `y`. Note that `VerticalAlignment::Bottom` instead places the bottom edge of the
+/// descender at `y`, which is one pixel lower.
+pub struct FontStyle<'a, Image, ColorType> {
+    pub font: &'a Font<'a, Image>,
+    pub color: ColorType,
+}
+
+impl<'a, Image, ColorType> FontStyle<'a, Image, ColorType> {
+    pub fn new(font: &'a Font<'a, Image>, color: ColorType) -> Self {
+        Self { font, color }
+    }
+}
+
+impl<'a, Image, ColorType: Copy> Clone for FontStyle<'a, Image, ColorType> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, Image, ColorType: Copy> Copy for FontStyle<'a, Image, ColorType> {}
+
+impl<'a, Image, ColorType> FontStyle<'a, Image, ColorType>
+where
+    Image: MonoImageData,
+{
+    /// Returns the y coordinate of the top of the ascender.
+    fn top(&self, y: i32, baseline: Baseline) -> i32 {
+        let ascender = self.font.ascender as i32;
+        let descender = self.font.descender as i32;
+        match baseline {
+            Baseline::Top => y,
+            Baseline::Middle => y - (ascender + descender) / 2,
+            Baseline::Alphabetic => y - ascender,
+            Baseline::Bottom => y - (ascender + descender) + 1,
+        }
+    }
+}
+
+impl<'a, Image, ColorType> TextRenderer for FontStyle<'a, Image, ColorType>
+where
+    Image: MonoImageData,
+    ColorType: PixelColor,
+{
+    type Color = ColorType;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let top = self.top(position.y, baseline);
+        let mut x = position.x;
+        let mut previous = None;
+        for c in text.chars() {
+            let (glyph, kerning) = match self.font.next_glyph(&mut previous, c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            x += kerning;
+            let left = x + glyph.image_left as i32;
+            let glyph_top = top + self.font.ascender as i32 - glyph.image_top as i32;
+            let image = &glyph.image;
+            let pixels = (0..image.height()).flat_map(|y| {
+                (0..image.width()).filter_map(move |x| {
+                    if image.get_pixel(x, y) {
+                        Some(Pixel(
+                            Point::new(left + x as i32, glyph_top + y as i32),
+                            self.color,
+                        ))
+                    } else {
+                        None
+                    }
+                })
+            });
+            target.draw_iter(pixels)?;
+            x += glyph.advance as i32;
+        }
+        Ok(Point::new(x, position.y))
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        _baseline: Baseline,
+        _target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        Ok(position + Point::new(width as i32, 0))
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let (width, height) = self.font.get_text_size(text);
+        TextMetrics {
+            bounding_box: Rectangle::new(
+                Point::new(position.x, self.top(position.y, baseline)),
+                Size::new(width, height),
+            ),
+            next_position: position + Point::new(width as i32, 0),
+        }
+    }
+
+    fn line_height(&self) -> u32 {
+        (self.font.ascender + self.font.descender) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_graphics::image::{Image, ImageRaw};
+    use embedded_graphics::pixelcolor::{self, BinaryColor};
+    use embedded_graphics::prelude::*;
+    use embedded_graphics::primitives::{Line, PrimitiveStyle, Rectangle};
+
+    use embedded_graphics::text::{Baseline, Text};
+
+    use super::super::color::BlackWhite::{self, Black, White};
+    use super::super::color::{Gray2, Gray4};
+    use super::super::font::{test_font, test_glyph, Font};
+    use super::super::image::{MonoBitmapImage, MonoRLEImage};
+    use super::super::{Frame, Renderer};
+    use super::FontStyle;
+
+    #[test]
+    fn test_draw_target() {
+        let frame = Frame::new(16, 4, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            Rectangle::new(Point::new(2, 0), Size::new(4, 2))
+                .into_styled(PrimitiveStyle::with_fill(White))
+                .draw(&mut renderer)
+                .unwrap();
+            Line::new(Point::new(8, 0), Point::new(11, 3))
+                .into_styled(PrimitiveStyle::with_stroke(White, 1))
+                .draw(&mut renderer)
+                .unwrap();
+        });
+        let mut buffer = [0u8; 8];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x3c, 0x80, 0x3c, 0x40, 0x00, 0x20, 0x00, 0x10]);
+
+        // Only the last two rows.
+        let mut buffer = [0u8; 4];
+        frame.draw_part(2, &mut buffer);
+        assert_eq!(buffer, [0x00, 0x20, 0x00, 0x10]);
+    }
+
+    #[test]
+    fn test_gray_draw_target() {
+        let frame = Frame::new(8, 1, |mut renderer: Renderer<Gray2>| {
+            renderer.clear(Gray2(0));
+            let color = pixelcolor::Gray2::new(2).into();
+            Rectangle::new(Point::new(2, 0), Size::new(3, 1))
+                .into_styled(PrimitiveStyle::with_fill(color))
+                .draw(&mut renderer)
+                .unwrap();
+            Pixel(Point::new(7, 0), Gray2(3))
+                .draw(&mut renderer)
+                .unwrap();
+        });
+        let mut buffer = [0u8; 2];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x0a, 0x83]);
+
+        assert_eq!(Gray4::from(pixelcolor::Gray4::new(9)), Gray4(9));
+        assert_eq!(pixelcolor::Gray4::from(Gray4(9)), pixelcolor::Gray4::new(9));
+    }
+
+    #[test]
+    fn test_fill_contiguous() {
+        let data = [0b1100_1010, 0b0000_0000, 0b0011_1111, 0b1100_0000];
+        let raw = ImageRaw::<BinaryColor>::new(&data, 10);
+        let frame = Frame::new(16, 2, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            Image::new(&raw, Point::new(3, 0))
+                .draw(&mut renderer.color_converted())
+                .unwrap();
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x19, 0x40, 0x07, 0xf8]);
+
+        // The colors end within the first row.
+        let frame = Frame::new(8, 2, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let area = Rectangle::new(Point::new(2, 0), Size::new(6, 2));
+            renderer.fill_contiguous(&area, [White; 3]).unwrap();
+        });
+        let mut buffer = [0u8; 2];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x38, 0x00]);
+    }
+
+    const IMAGE: MonoRLEImage = MonoRLEImage {
+        data: &[3, 5, 7, 0x0001, 0x8002, 0x8001, 0x0002],
+        width: 3,
+        height: 2,
+    };
+
+    #[test]
+    fn test_image_drawable() {
+        let frame = Frame::new(8, 3, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            Image::new(&IMAGE, Point::new(2, 1))
+                .draw(&mut renderer.color_converted())
+                .unwrap();
+        });
+        let mut buffer = [0u8; 3];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x00, 0x18, 0x20]);
+    }
+
+    const FONT: Font<MonoBitmapImage> =
+        test_font(3, 1, &[test_glyph(&[0x80, 0xc0], 2, 1, 2, 3)], |c| {
+            if c == 'a' {
+                Some(0)
+            } else {
+                None
+            }
+        });
+
+    #[test]
+    fn test_text_renderer() {
+        let frame = Frame::new(8, 4, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let style = FontStyle::new(&FONT, White);
+            let end = Text::with_baseline("a?a", Point::new(1, 3), style, Baseline::Alphabetic)
+                .draw(&mut renderer)
+                .unwrap();
+            assert_eq!(end, Point::new(7, 3));
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x00, 0x24, 0x36, 0x00]);
+    }
+}
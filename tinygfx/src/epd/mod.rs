@@ -0,0 +1,155 @@
+//! Command sequences for common e-paper controllers.
+//!
+//! The image data is streamed part by part via `interface::send_window()`, so no full frame
+//! buffer is required. The drivers do not read the BUSY signal of the controller themselves.
+//! Instead, `Busy::wait()` is called whenever the controller has to finish an operation.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use super::color::Color;
+use super::interface::send_window;
+use super::{DirtyRect, Frame, Layout, Renderer};
+
+pub mod ssd1608;
+pub mod ssd1680;
+pub mod uc8151;
+
+pub use ssd1608::Ssd1608;
+pub use ssd1680::Ssd1680;
+pub use uc8151::Uc8151;
+
+/// Waits until the controller is not busy anymore, usually by polling its BUSY pin.
+pub trait Busy {
+    fn wait(&mut self);
+}
+
+impl<F: FnMut()> Busy for F {
+    fn wait(&mut self) {
+        self()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Refresh {
+    /// Refreshes the whole display with the full waveform.
+    Full,
+    /// Only refreshes the window set via `Controller::set_window()`, usually with a faster
+    /// waveform and less flickering.
+    Partial,
+}
+
+pub trait Controller {
+    fn width(&self) -> u32;
+    fn height(&self) -> u32;
+
+    /// Initializes the controller after a hardware reset.
+    fn init<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy;
+
+    /// Restricts the following RAM write (and, for some controllers, the refresh) to a window.
+    ///
+    /// The horizontal bounds of the window have to be multiples of 8.
+    fn set_window<Interface>(
+        &self,
+        interface: &mut Interface,
+        window: DirtyRect,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand;
+
+    /// Starts writing the new image data into the RAM of the controller.
+    fn start_write<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand;
+
+    /// Shows the content of the RAM on the display.
+    fn refresh<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+        mode: Refresh,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy;
+
+    /// Enters deep sleep. A hardware reset is required to wake up the controller.
+    fn sleep<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand;
+
+    /// Returns the window which covers the whole display.
+    ///
+    /// The RAM of the controllers is addressed in bytes, so the right edge is rounded up to a
+    /// multiple of 8 like the windows of partial updates.
+    fn full_window(&self) -> DirtyRect {
+        DirtyRect {
+            left: 0,
+            top: 0,
+            right: (self.width() + 7) & !7,
+            bottom: self.height(),
+        }
+    }
+}
+
+/// Renders the frame, writes it into the RAM of the controller and refreshes the display.
+///
+/// If `window` is specified (e.g., as returned by `Frame::draw_part_dirty()`), only that part of
+/// the display is written and a partial refresh is performed. The frame needs to have the size
+/// of the display.
+///
+/// Panics if the frame does not use `Layout::Rows`.
+pub fn update<C, Interface, B, Draw, ColorType>(
+    controller: &C,
+    interface: &mut Interface,
+    busy: &mut B,
+    frame: &Frame<Draw, ColorType>,
+    buffer: &mut [u8],
+    window: Option<DirtyRect>,
+) -> Result<(), DisplayError>
+where
+    C: Controller,
+    Interface: WriteOnlyDataCommand,
+    B: Busy,
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+{
+    assert_eq!(frame.layout, Layout::Rows);
+    let (window, mode) = match window {
+        Some(window) => (
+            DirtyRect {
+                left: window.left & !7,
+                top: window.top,
+                right: (window.right + 7) & !7,
+                bottom: window.bottom,
+            },
+            Refresh::Partial,
+        ),
+        None => (controller.full_window(), Refresh::Full),
+    };
+    controller.set_window(interface, window)?;
+    controller.start_write(interface)?;
+    send_window(frame, interface, buffer, window)?;
+    controller.refresh(interface, busy, mode)
+}
+
+fn command<Interface>(
+    interface: &mut Interface,
+    command: u8,
+    data: &[u8],
+) -> Result<(), DisplayError>
+where
+    Interface: WriteOnlyDataCommand,
+{
+    interface.send_commands(DataFormat::U8(&[command]))?;
+    if !data.is_empty() {
+        interface.send_data(DataFormat::U8(data))?;
+    }
+    Ok(())
+}
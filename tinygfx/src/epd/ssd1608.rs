@@ -0,0 +1,204 @@
+//! Solomon Systech SSD1608 (and the compatible IL3829), e.g., used in older 1.54" and 2.13"
+//! black/white panels.
+//!
+//! The controller does not contain waveforms in OTP, so the lookup table is written before
+//! every refresh.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use super::super::DirtyRect;
+use super::{command, Busy, Controller, Refresh};
+
+const DRIVER_OUTPUT_CONTROL: u8 = 0x01;
+const BOOSTER_SOFT_START_CONTROL: u8 = 0x0c;
+const DEEP_SLEEP_MODE: u8 = 0x10;
+const DATA_ENTRY_MODE: u8 = 0x11;
+const SW_RESET: u8 = 0x12;
+const MASTER_ACTIVATION: u8 = 0x20;
+const DISPLAY_UPDATE_CONTROL_2: u8 = 0x22;
+const WRITE_RAM: u8 = 0x24;
+const WRITE_VCOM_REGISTER: u8 = 0x2c;
+const WRITE_LUT_REGISTER: u8 = 0x32;
+const SET_DUMMY_LINE_PERIOD: u8 = 0x3a;
+const SET_GATE_LINE_WIDTH: u8 = 0x3b;
+const SET_RAM_X_ADDRESS: u8 = 0x44;
+const SET_RAM_Y_ADDRESS: u8 = 0x45;
+const SET_RAM_X_COUNTER: u8 = 0x4e;
+const SET_RAM_Y_COUNTER: u8 = 0x4f;
+const TERMINATE_FRAME_READ_WRITE: u8 = 0xff;
+
+const LUT_FULL_UPDATE: [u8; 30] = [
+    0x02, 0x02, 0x01, 0x11, 0x12, 0x12, 0x22, 0x22, 0x66, 0x69, 0x69, 0x59, 0x58, 0x99, 0x99, 0x88,
+    0x00, 0x00, 0x00, 0x00, 0xf8, 0xb4, 0x13, 0x51, 0x35, 0x51, 0x51, 0x19, 0x01, 0x00,
+];
+
+const LUT_PARTIAL_UPDATE: [u8; 30] = [
+    0x10, 0x18, 0x18, 0x08, 0x18, 0x18, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x13, 0x14, 0x44, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+pub struct Ssd1608 {
+    width: u32,
+    height: u32,
+}
+
+impl Ssd1608 {
+    /// Creates a driver for a panel with `width` sources and `height` gates.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Controller for Ssd1608 {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn init<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        command(interface, SW_RESET, &[])?;
+        busy.wait();
+        let gates = self.height - 1;
+        command(
+            interface,
+            DRIVER_OUTPUT_CONTROL,
+            &[gates as u8, (gates >> 8) as u8, 0x00],
+        )?;
+        command(interface, BOOSTER_SOFT_START_CONTROL, &[0xd7, 0xd6, 0x9d])?;
+        command(interface, WRITE_VCOM_REGISTER, &[0xa8])?;
+        command(interface, SET_DUMMY_LINE_PERIOD, &[0x1a])?;
+        command(interface, SET_GATE_LINE_WIDTH, &[0x08])?;
+        // Increment x and y.
+        command(interface, DATA_ENTRY_MODE, &[0x03])
+    }
+
+    fn set_window<Interface>(
+        &self,
+        interface: &mut Interface,
+        window: DirtyRect,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        assert_eq!(window.left & 7, 0);
+        assert_eq!(window.right & 7, 0);
+        let (left, right) = ((window.left >> 3) as u8, ((window.right >> 3) - 1) as u8);
+        let (top, bottom) = (window.top, window.bottom - 1);
+        command(interface, SET_RAM_X_ADDRESS, &[left, right])?;
+        command(
+            interface,
+            SET_RAM_Y_ADDRESS,
+            &[
+                top as u8,
+                (top >> 8) as u8,
+                bottom as u8,
+                (bottom >> 8) as u8,
+            ],
+        )?;
+        command(interface, SET_RAM_X_COUNTER, &[left])?;
+        command(interface, SET_RAM_Y_COUNTER, &[top as u8, (top >> 8) as u8])
+    }
+
+    fn start_write<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, WRITE_RAM, &[])
+    }
+
+    fn refresh<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+        mode: Refresh,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        let lut = match mode {
+            Refresh::Full => &LUT_FULL_UPDATE,
+            Refresh::Partial => &LUT_PARTIAL_UPDATE,
+        };
+        command(interface, WRITE_LUT_REGISTER, lut)?;
+        command(interface, DISPLAY_UPDATE_CONTROL_2, &[0xc4])?;
+        command(interface, MASTER_ACTIVATION, &[])?;
+        command(interface, TERMINATE_FRAME_READ_WRITE, &[])?;
+        busy.wait();
+        Ok(())
+    }
+
+    fn sleep<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, DEEP_SLEEP_MODE, &[0x01])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::super::super::color::BlackWhite::White;
+    use super::super::super::mock::{RecordingInterface, Transfer};
+    use super::super::super::Frame;
+    use super::super::{update, Controller};
+    use super::{Ssd1608, LUT_FULL_UPDATE};
+
+    #[test]
+    fn test_full_update() {
+        let controller = Ssd1608::new(16, 2);
+        let mut interface = RecordingInterface::new();
+        let mut busy = interface.busy();
+        let frame = Frame::new(16, 2, |mut renderer| renderer.clear(White));
+        let mut buffer = [0u8; 4];
+        update(
+            &controller,
+            &mut interface,
+            &mut busy,
+            &frame,
+            &mut buffer,
+            None,
+        )
+        .unwrap();
+        controller.sleep(&mut interface).unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Command(vec![0x44]),
+                Transfer::Data(vec![0x00, 0x01]),
+                Transfer::Command(vec![0x45]),
+                Transfer::Data(vec![0x00, 0x00, 0x01, 0x00]),
+                Transfer::Command(vec![0x4e]),
+                Transfer::Data(vec![0x00]),
+                Transfer::Command(vec![0x4f]),
+                Transfer::Data(vec![0x00, 0x00]),
+                Transfer::Command(vec![0x24]),
+                Transfer::Data(vec![0xff; 4]),
+                Transfer::Command(vec![0x32]),
+                Transfer::Data(LUT_FULL_UPDATE.to_vec()),
+                Transfer::Command(vec![0x22]),
+                Transfer::Data(vec![0xc4]),
+                Transfer::Command(vec![0x20]),
+                Transfer::Command(vec![0xff]),
+                Transfer::WaitBusy,
+                Transfer::Command(vec![0x10]),
+                Transfer::Data(vec![0x01]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,258 @@
+//! Solomon Systech SSD1680, e.g., used in 2.13" and 2.9" black/white panels.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use super::super::DirtyRect;
+use super::{command, Busy, Controller, Refresh};
+
+const DRIVER_OUTPUT_CONTROL: u8 = 0x01;
+const DEEP_SLEEP_MODE: u8 = 0x10;
+const DATA_ENTRY_MODE: u8 = 0x11;
+const SW_RESET: u8 = 0x12;
+const TEMPERATURE_SENSOR_CONTROL: u8 = 0x18;
+const MASTER_ACTIVATION: u8 = 0x20;
+const DISPLAY_UPDATE_CONTROL_1: u8 = 0x21;
+const DISPLAY_UPDATE_CONTROL_2: u8 = 0x22;
+const WRITE_RAM_BW: u8 = 0x24;
+const BORDER_WAVEFORM_CONTROL: u8 = 0x3c;
+const SET_RAM_X_ADDRESS: u8 = 0x44;
+const SET_RAM_Y_ADDRESS: u8 = 0x45;
+const SET_RAM_X_COUNTER: u8 = 0x4e;
+const SET_RAM_Y_COUNTER: u8 = 0x4f;
+
+pub struct Ssd1680 {
+    width: u32,
+    height: u32,
+}
+
+impl Ssd1680 {
+    /// Creates a driver for a panel with `width` sources and `height` gates.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Controller for Ssd1680 {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn init<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        command(interface, SW_RESET, &[])?;
+        busy.wait();
+        let gates = self.height - 1;
+        command(
+            interface,
+            DRIVER_OUTPUT_CONTROL,
+            &[gates as u8, (gates >> 8) as u8, 0x00],
+        )?;
+        // Increment x and y.
+        command(interface, DATA_ENTRY_MODE, &[0x03])?;
+        command(interface, BORDER_WAVEFORM_CONTROL, &[0x05])?;
+        command(interface, DISPLAY_UPDATE_CONTROL_1, &[0x00, 0x80])?;
+        // Use the internal temperature sensor.
+        command(interface, TEMPERATURE_SENSOR_CONTROL, &[0x80])?;
+        busy.wait();
+        Ok(())
+    }
+
+    fn set_window<Interface>(
+        &self,
+        interface: &mut Interface,
+        window: DirtyRect,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        assert_eq!(window.left & 7, 0);
+        assert_eq!(window.right & 7, 0);
+        let (left, right) = ((window.left >> 3) as u8, ((window.right >> 3) - 1) as u8);
+        let (top, bottom) = (window.top, window.bottom - 1);
+        command(interface, SET_RAM_X_ADDRESS, &[left, right])?;
+        command(
+            interface,
+            SET_RAM_Y_ADDRESS,
+            &[
+                top as u8,
+                (top >> 8) as u8,
+                bottom as u8,
+                (bottom >> 8) as u8,
+            ],
+        )?;
+        command(interface, SET_RAM_X_COUNTER, &[left])?;
+        command(interface, SET_RAM_Y_COUNTER, &[top as u8, (top >> 8) as u8])
+    }
+
+    fn start_write<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, WRITE_RAM_BW, &[])
+    }
+
+    fn refresh<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+        mode: Refresh,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        let sequence = match mode {
+            Refresh::Full => 0xf7,
+            Refresh::Partial => 0xff,
+        };
+        command(interface, DISPLAY_UPDATE_CONTROL_2, &[sequence])?;
+        command(interface, MASTER_ACTIVATION, &[])?;
+        busy.wait();
+        Ok(())
+    }
+
+    fn sleep<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, DEEP_SLEEP_MODE, &[0x01])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::super::super::color::BlackWhite::{Black, White};
+    use super::super::super::mock::{RecordingInterface, Transfer};
+    use super::super::super::{DirtyRect, Frame};
+    use super::super::{update, Controller};
+    use super::Ssd1680;
+
+    #[test]
+    fn test_update() {
+        let controller = Ssd1680::new(16, 300);
+        let mut interface = RecordingInterface::new();
+        let mut busy = interface.busy();
+        controller.init(&mut interface, &mut busy).unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Command(vec![0x12]),
+                Transfer::WaitBusy,
+                Transfer::Command(vec![0x01]),
+                Transfer::Data(vec![0x2b, 0x01, 0x00]),
+                Transfer::Command(vec![0x11]),
+                Transfer::Data(vec![0x03]),
+                Transfer::Command(vec![0x3c]),
+                Transfer::Data(vec![0x05]),
+                Transfer::Command(vec![0x21]),
+                Transfer::Data(vec![0x00, 0x80]),
+                Transfer::Command(vec![0x18]),
+                Transfer::Data(vec![0x80]),
+                Transfer::WaitBusy,
+            ]
+        );
+
+        let frame = Frame::new(16, 300, |mut renderer| {
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 8, 257, 16, 258, Black);
+        });
+        let window = DirtyRect {
+            left: 9,
+            top: 257,
+            right: 10,
+            bottom: 258,
+        };
+        let mut buffer = [0u8; 2];
+        interface.clear();
+        update(
+            &controller,
+            &mut interface,
+            &mut busy,
+            &frame,
+            &mut buffer,
+            Some(window),
+        )
+        .unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Command(vec![0x44]),
+                Transfer::Data(vec![0x01, 0x01]),
+                Transfer::Command(vec![0x45]),
+                Transfer::Data(vec![0x01, 0x01, 0x01, 0x01]),
+                Transfer::Command(vec![0x4e]),
+                Transfer::Data(vec![0x01]),
+                Transfer::Command(vec![0x4f]),
+                Transfer::Data(vec![0x01, 0x01]),
+                Transfer::Command(vec![0x24]),
+                Transfer::Data(vec![0x00]),
+                Transfer::Command(vec![0x22]),
+                Transfer::Data(vec![0xff]),
+                Transfer::Command(vec![0x20]),
+                Transfer::WaitBusy,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_full_update_unaligned_width() {
+        // 2.13" panels have 122 sources, which are written as 16 bytes per row.
+        let controller = Ssd1680::new(122, 2);
+        let mut interface = RecordingInterface::new();
+        let mut busy = interface.busy();
+        let frame = Frame::new(122, 2, |mut renderer| {
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 120, 1, 122, 2, Black);
+        });
+        let mut buffer = [0u8; 32];
+        update(
+            &controller,
+            &mut interface,
+            &mut busy,
+            &frame,
+            &mut buffer,
+            None,
+        )
+        .unwrap();
+        // The padding bits of each row are unset.
+        let mut data = vec![0xff; 32];
+        data[15] = 0xc0;
+        data[31] = 0x00;
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Command(vec![0x44]),
+                Transfer::Data(vec![0x00, 0x0f]),
+                Transfer::Command(vec![0x45]),
+                Transfer::Data(vec![0x00, 0x00, 0x01, 0x00]),
+                Transfer::Command(vec![0x4e]),
+                Transfer::Data(vec![0x00]),
+                Transfer::Command(vec![0x4f]),
+                Transfer::Data(vec![0x00, 0x00]),
+                Transfer::Command(vec![0x24]),
+                Transfer::Data(data),
+                Transfer::Command(vec![0x22]),
+                Transfer::Data(vec![0xf7]),
+                Transfer::Command(vec![0x20]),
+                Transfer::WaitBusy,
+            ]
+        );
+    }
+}
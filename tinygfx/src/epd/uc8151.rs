@@ -0,0 +1,215 @@
+//! UltraChip UC8151 (and the compatible IL0373), e.g., used in 2.13" and 2.9" black/white
+//! panels.
+//!
+//! The controller is operated in black/white mode with the waveforms from OTP.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use super::super::DirtyRect;
+use super::{command, Busy, Controller, Refresh};
+
+const PANEL_SETTING: u8 = 0x00;
+const POWER_OFF: u8 = 0x02;
+const POWER_ON: u8 = 0x04;
+const BOOSTER_SOFT_START: u8 = 0x06;
+const DEEP_SLEEP: u8 = 0x07;
+const DATA_START_TRANSMISSION_2: u8 = 0x13;
+const DISPLAY_REFRESH: u8 = 0x12;
+const VCOM_AND_DATA_INTERVAL_SETTING: u8 = 0x50;
+const RESOLUTION_SETTING: u8 = 0x61;
+const PARTIAL_WINDOW: u8 = 0x90;
+const PARTIAL_IN: u8 = 0x91;
+const PARTIAL_OUT: u8 = 0x92;
+
+pub struct Uc8151 {
+    width: u32,
+    height: u32,
+}
+
+impl Uc8151 {
+    /// Creates a driver for a panel with `width` sources and `height` gates.
+    ///
+    /// Panics if `width` does not fit into the 8-bit source registers of the controller.
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(width < 256);
+        Self { width, height }
+    }
+}
+
+impl Controller for Uc8151 {
+    fn width(&self) -> u32 {
+        self.width
+    }
+
+    fn height(&self) -> u32 {
+        self.height
+    }
+
+    fn init<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        command(interface, BOOSTER_SOFT_START, &[0x17, 0x17, 0x17])?;
+        command(interface, POWER_ON, &[])?;
+        busy.wait();
+        // Black/white mode, LUT from OTP.
+        command(interface, PANEL_SETTING, &[0x1f])?;
+        command(
+            interface,
+            RESOLUTION_SETTING,
+            &[
+                self.width as u8,
+                (self.height >> 8) as u8,
+                self.height as u8,
+            ],
+        )?;
+        command(interface, VCOM_AND_DATA_INTERVAL_SETTING, &[0x97])
+    }
+
+    fn set_window<Interface>(
+        &self,
+        interface: &mut Interface,
+        window: DirtyRect,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        assert_eq!(window.left & 7, 0);
+        assert_eq!(window.right & 7, 0);
+        if window == self.full_window() {
+            return command(interface, PARTIAL_OUT, &[]);
+        }
+        let (left, right) = (window.left as u8, (window.right - 1) as u8);
+        let (top, bottom) = (window.top, window.bottom - 1);
+        command(interface, PARTIAL_IN, &[])?;
+        command(
+            interface,
+            PARTIAL_WINDOW,
+            &[
+                left,
+                right,
+                (top >> 8) as u8,
+                top as u8,
+                (bottom >> 8) as u8,
+                bottom as u8,
+                // Only scan the gates within the window.
+                0x01,
+            ],
+        )
+    }
+
+    fn start_write<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, DATA_START_TRANSMISSION_2, &[])
+    }
+
+    fn refresh<Interface, B>(
+        &self,
+        interface: &mut Interface,
+        busy: &mut B,
+        _mode: Refresh,
+    ) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+        B: Busy,
+    {
+        // The OTP waveform is used for both modes, the partial refresh is only restricted to the
+        // window.
+        command(interface, DISPLAY_REFRESH, &[])?;
+        busy.wait();
+        Ok(())
+    }
+
+    fn sleep<Interface>(&self, interface: &mut Interface) -> Result<(), DisplayError>
+    where
+        Interface: WriteOnlyDataCommand,
+    {
+        command(interface, POWER_OFF, &[])?;
+        command(interface, DEEP_SLEEP, &[0xa5])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::super::super::color::BlackWhite::{Black, White};
+    use super::super::super::mock::{RecordingInterface, Transfer};
+    use super::super::super::{DirtyRect, Frame, Layout};
+    use super::super::update;
+    use super::Uc8151;
+
+    #[test]
+    fn test_partial_update() {
+        let controller = Uc8151::new(24, 4);
+        let mut interface = RecordingInterface::new();
+        let mut busy = interface.busy();
+        let frame = Frame::new(24, 4, |mut renderer| {
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 8, 2, 12, 3, Black);
+        });
+        let window = DirtyRect {
+            left: 8,
+            top: 2,
+            right: 12,
+            bottom: 3,
+        };
+        let mut buffer = [0u8; 3];
+        update(
+            &controller,
+            &mut interface,
+            &mut busy,
+            &frame,
+            &mut buffer,
+            Some(window),
+        )
+        .unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Command(vec![0x91]),
+                Transfer::Command(vec![0x90]),
+                Transfer::Data(vec![0x08, 0x0f, 0x00, 0x02, 0x00, 0x02, 0x01]),
+                Transfer::Command(vec![0x13]),
+                Transfer::Data(vec![0x0f]),
+                Transfer::Command(vec![0x12]),
+                Transfer::WaitBusy,
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_page_layout() {
+        let controller = Uc8151::new(24, 8);
+        let mut interface = RecordingInterface::new();
+        let mut busy = interface.busy();
+        let mut frame = Frame::new(24, 8, |mut renderer| renderer.clear(White));
+        frame.layout(Layout::Pages);
+        let mut buffer = [0u8; 24];
+        let _ = update(
+            &controller,
+            &mut interface,
+            &mut busy,
+            &frame,
+            &mut buffer,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_width_limit() {
+        Uc8151::new(256, 8);
+    }
+}
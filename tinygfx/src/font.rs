@@ -1,4 +1,8 @@
+use core::cmp::{max, Ordering};
+
 use super::color::Color;
+#[cfg(test)]
+use super::image::MonoBitmapImage;
 use super::image::MonoImageData;
 use super::{Clip, Renderer};
 
@@ -6,24 +10,108 @@ pub struct Font<'a, Image> {
     pub ascender: u16,
     pub descender: u16,
     pub glyphs: &'a [Glyph<Image>],
-    pub get_glyph_index: fn(c: char) -> Option<usize>,
+    /// Mapping from characters to indices within `glyphs`, either a table of character ranges
+    /// or a function.
+    pub glyph_index: GlyphIndex<'a>,
+    /// Kerning pairs, sorted by the glyph indices.
+    pub kerning: &'a [Kerning],
+    /// Index of the glyph which is drawn for characters which are neither part of this font
+    /// nor of any fallback font.
+    pub replacement: Option<usize>,
+    /// Font which provides the characters which are missing in this font.
+    ///
+    /// Fallback fonts can be added to a generated font via `Font { fallback: Some(&OTHER),
+    /// ..FONT }`. The fallback font has to use the same image type as this font, so a font with
+    /// gray glyphs cannot fall back to a font with monochrome glyphs or vice versa.
+    pub fallback: Option<&'a Font<'a, Image>>,
 }
 
 impl<'a, Image> Font<'a, Image>
 where
     Image: MonoImageData,
 {
+    /// Returns the index of the glyph for the character within `glyphs`.
+    pub fn get_glyph_index(&self, c: char) -> Option<usize> {
+        match self.glyph_index {
+            GlyphIndex::Ranges(ranges) => {
+                let c = c as u32;
+                let index = ranges
+                    .binary_search_by(|range| {
+                        if c < range.start {
+                            Ordering::Greater
+                        } else if c >= range.start + range.len as u32 {
+                            Ordering::Less
+                        } else {
+                            Ordering::Equal
+                        }
+                    })
+                    .ok()?;
+                let range = &ranges[index];
+                Some(range.index as usize + (c - range.start) as usize)
+            }
+            GlyphIndex::Function(get_glyph_index) => get_glyph_index(c),
+        }
+    }
+
+    /// Returns the glyph for the character, taken from the font itself, from one of the
+    /// fallback fonts, or the replacement glyph.
+    pub fn get_glyph(&self, c: char) -> Option<&Glyph<Image>> {
+        self.next_glyph(&mut None, c).map(|(glyph, _)| glyph)
+    }
+
+    /// Returns the horizontal offset between the glyphs with the indices `left` and `right`
+    /// when they are drawn next to each other.
+    pub fn get_kerning(&self, left: usize, right: usize) -> i32 {
+        self.kerning
+            .binary_search_by_key(&(left, right), |pair| {
+                (pair.left as usize, pair.right as usize)
+            })
+            .map_or(0, |index| self.kerning[index].offset as i32)
+    }
+
     pub fn get_text_size(&self, text: &str) -> (u32, u32) {
         let mut width = 0;
+        let mut previous = None;
         for c in text.chars() {
-            let index = (self.get_glyph_index)(c);
-            if index.is_none() {
-                continue;
+            if let Some((glyph, kerning)) = self.next_glyph(&mut previous, c) {
+                width += kerning + glyph.advance as i32;
             }
-            let glyph = &self.glyphs[index.unwrap()];
-            width += glyph.advance;
         }
-        (width, (self.ascender + self.descender) as u32)
+        (
+            max(width, 0) as u32,
+            (self.ascender + self.descender) as u32,
+        )
+    }
+
+    /// Returns the longest prefix of the text which fits into `width` pixels when followed by
+    /// `suffix`. Spaces at the end of the prefix are removed.
+    pub fn truncate<'b>(&self, text: &'b str, width: u32, suffix: &str) -> &'b str {
+        let mut remaining = width as i32 - self.get_text_size(suffix).0 as i32;
+        let mut end = text.len();
+        let mut previous = None;
+        for (i, c) in text.char_indices() {
+            let advance = self
+                .next_glyph(&mut previous, c)
+                .map_or(0, |(glyph, kerning)| kerning + glyph.advance as i32);
+            if advance > remaining {
+                end = i;
+                break;
+            }
+            remaining -= advance;
+        }
+        text[..end].trim_end_matches(is_space)
+    }
+
+    /// Splits the text into lines which are at most `width` pixels wide.
+    ///
+    /// Lines are broken at explicit newlines and at spaces. Words which do not fit into a line
+    /// on their own are broken between characters.
+    pub fn lines<'b>(&'a self, text: &'b str, width: u32) -> Lines<'a, 'b, Image> {
+        Lines {
+            font: self,
+            text: Some(text),
+            width,
+        }
     }
 
     pub fn render<ColorType: Color>(
@@ -36,19 +124,49 @@ where
         color: ColorType,
     ) {
         let mut pos = x;
+        let mut previous = None;
         // TODO: Discard glyphs if they are definitely not shown.
         for c in text.chars() {
-            let index = (self.get_glyph_index)(c);
-            if index.is_none() {
-                continue;
-            }
-            let glyph = &self.glyphs[index.unwrap()];
+            let (glyph, kerning) = match self.next_glyph(&mut previous, c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            pos += kerning;
             self.render_glyph(renderer, clip, glyph, pos, y, color);
             pos += glyph.advance as i32;
         }
         //}
     }
 
+    /// Returns the glyph for `c` and the kerning between the glyph and the previous glyph.
+    ///
+    /// `previous` contains the index of the previous glyph and is updated for the next call.
+    /// Kerning is only applied between glyphs of this font, not between glyphs of fallback
+    /// fonts.
+    pub(crate) fn next_glyph(
+        &self,
+        previous: &mut Option<usize>,
+        c: char,
+    ) -> Option<(&Glyph<Image>, i32)> {
+        let index = match self.get_glyph_index(c) {
+            Some(index) => index,
+            None => {
+                let mut fallback = self.fallback;
+                while let Some(font) = fallback {
+                    if let Some(index) = font.get_glyph_index(c) {
+                        *previous = None;
+                        return Some((&font.glyphs[index], 0));
+                    }
+                    fallback = font.fallback;
+                }
+                self.replacement?
+            }
+        };
+        let kerning = previous.map_or(0, |previous| self.get_kerning(previous, index));
+        *previous = Some(index);
+        Some((&self.glyphs[index], kerning))
+    }
+
     fn render_glyph<ColorType: Color>(
         &self,
         renderer: &mut Renderer<ColorType>,
@@ -64,9 +182,285 @@ where
     }
 }
 
+/// Iterator over the lines of a wrapped text, returned by `Font::lines()`.
+pub struct Lines<'a, 'b, Image> {
+    font: &'a Font<'a, Image>,
+    text: Option<&'b str>,
+    width: u32,
+}
+
+impl<'a, 'b, Image> Iterator for Lines<'a, 'b, Image>
+where
+    Image: MonoImageData,
+{
+    type Item = &'b str;
+
+    fn next(&mut self) -> Option<&'b str> {
+        let text = self.text?;
+        let mut width = 0;
+        let mut word_break = None;
+        let mut previous_space = true;
+        let mut previous = None;
+        for (i, c) in text.char_indices() {
+            if c == '\n' {
+                self.text = Some(&text[i + 1..]);
+                return Some(&text[..i]);
+            }
+            let advance = self
+                .font
+                .next_glyph(&mut previous, c)
+                .map_or(0, |(glyph, kerning)| kerning + glyph.advance as i32);
+            if is_space(c) {
+                if !previous_space {
+                    word_break = Some(i);
+                }
+                previous_space = true;
+            } else {
+                // Trailing spaces are removed, so only other characters can overflow the line.
+                if width + advance > self.width as i32 && i != 0 {
+                    if word_break.is_none() && previous_space {
+                        // Only leading spaces precede the first word, which is wrapped like
+                        // a line without them instead of emitting an empty line.
+                        self.text = Some(&text[i..]);
+                        return self.next();
+                    }
+                    let (end, start) = word_break.map_or((i, i), |end| (end, end));
+                    self.text = Some(text[start..].trim_start_matches(is_space));
+                    return Some(text[..end].trim_end_matches(is_space));
+                }
+                previous_space = false;
+            }
+            width += advance;
+        }
+        self.text = None;
+        Some(text)
+    }
+}
+
+fn is_space(c: char) -> bool {
+    c != '\n' && c.is_whitespace()
+}
+
+/// Mapping from characters to glyph indices.
+#[derive(Copy, Clone)]
+pub enum GlyphIndex<'a> {
+    /// Ranges of consecutive characters, sorted by the first character.
+    Ranges(&'a [GlyphRange]),
+    /// Custom function which returns the glyph index.
+    Function(fn(c: char) -> Option<usize>),
+}
+
+/// Consecutive characters which are mapped to consecutive glyphs.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct GlyphRange {
+    /// Code point of the first character.
+    pub start: u32,
+    /// Number of characters.
+    pub len: u16,
+    /// Glyph index of the first character.
+    pub index: u16,
+}
+
+/// Horizontal offset which is applied between two glyphs, in addition to the advance of the
+/// left glyph.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Kerning {
+    pub left: u16,
+    pub right: u16,
+    pub offset: i16,
+}
+
 pub struct Glyph<Image> {
     pub image: Image,
     pub image_left: i16,
     pub image_top: i16,
     pub advance: u32,
 }
+
+/// Returns a monochrome font for tests, without kerning, replacement or fallback.
+#[cfg(test)]
+pub(crate) const fn test_font(
+    ascender: u16,
+    descender: u16,
+    glyphs: &'static [Glyph<MonoBitmapImage>],
+    glyph_index: fn(c: char) -> Option<usize>,
+) -> Font<'static, MonoBitmapImage> {
+    Font {
+        ascender,
+        descender,
+        glyphs,
+        glyph_index: GlyphIndex::Function(glyph_index),
+        kerning: &[],
+        replacement: None,
+        fallback: None,
+    }
+}
+
+/// Returns a glyph for tests with one byte per row of the image.
+#[cfg(test)]
+pub(crate) const fn test_glyph(
+    data: &'static [u8],
+    width: u16,
+    image_left: i16,
+    image_top: i16,
+    advance: u32,
+) -> Glyph<MonoBitmapImage> {
+    Glyph {
+        image: MonoBitmapImage {
+            data,
+            width,
+            height: data.len() as u16,
+            stride: 1,
+        },
+        image_left,
+        image_top,
+        advance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::super::image::MonoBitmapImage;
+    use super::{test_font, test_glyph, Font, Glyph, GlyphIndex, GlyphRange, Kerning};
+
+    const FONT: Font<MonoBitmapImage> = test_font(2, 0, &[test_glyph(&[], 0, 0, 0, 2)], |c| {
+        if c.is_ascii() {
+            Some(0)
+        } else {
+            None
+        }
+    });
+
+    #[test]
+    fn test_lines() {
+        let lines = |text, width| FONT.lines(text, width).collect::<Vec<_>>();
+        assert_eq!(lines("aa aaaa a", 6), ["aa", "aaa", "a a"]);
+        assert_eq!(lines("a  a\n\naaa  ", 6), ["a", "a", "", "aaa  "]);
+        assert_eq!(lines("a aa\na", 100), ["a aa", "a"]);
+        assert_eq!(lines("aaa", 0), ["a", "a", "a"]);
+        assert_eq!(lines("", 6), [""]);
+        assert_eq!(lines("a\n   aaaa", 6), ["a", "aaa", "a"]);
+        assert_eq!(lines("   aaaa", 6), ["aaa", "a"]);
+        assert_eq!(lines("  a", 6), ["  a"]);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(FONT.truncate("aaaa", 8, ".."), "aa");
+        assert_eq!(FONT.truncate("a aa", 7, ".."), "a");
+        assert_eq!(FONT.truncate("aaaa", 100, ".."), "aaaa");
+        assert_eq!(FONT.truncate("aaaa", 2, ".."), "");
+    }
+
+    #[test]
+    fn test_kerning() {
+        const GLYPH: Glyph<MonoBitmapImage> = test_glyph(&[], 0, 0, 0, 2);
+        const KERNED: Font<MonoBitmapImage> = Font {
+            kerning: &[
+                Kerning {
+                    left: 0,
+                    right: 1,
+                    offset: -1,
+                },
+                Kerning {
+                    left: 1,
+                    right: 0,
+                    offset: 1,
+                },
+            ],
+            ..test_font(2, 0, &[GLYPH; 3], |c| match c {
+                'a' => Some(0),
+                'b' => Some(1),
+                ' ' => Some(2),
+                _ => None,
+            })
+        };
+        assert_eq!(KERNED.get_kerning(0, 1), -1);
+        assert_eq!(KERNED.get_kerning(1, 1), 0);
+        assert_eq!(KERNED.get_text_size("ab"), (3, 2));
+        assert_eq!(KERNED.get_text_size("aba"), (6, 2));
+        assert_eq!(KERNED.truncate("abab", 5, ""), "ab");
+        assert_eq!(KERNED.lines("ab ab", 6).collect::<Vec<_>>(), ["ab", "ab"]);
+        assert_eq!(KERNED.lines("abab", 6).collect::<Vec<_>>(), ["aba", "b"]);
+    }
+
+    #[test]
+    fn test_fallback() {
+        const fn glyph(advance: u32) -> Glyph<MonoBitmapImage> {
+            test_glyph(&[], 0, 0, 0, advance)
+        }
+        const SYMBOLS: Font<MonoBitmapImage> =
+            test_font(2, 0, &[glyph(5)], |c| if c == '*' { Some(0) } else { None });
+        const TEXT: Font<MonoBitmapImage> = Font {
+            kerning: &[Kerning {
+                left: 0,
+                right: 0,
+                offset: -1,
+            }],
+            replacement: Some(1),
+            fallback: Some(&SYMBOLS),
+            ..test_font(2, 0, &[glyph(2), glyph(3)], |c| {
+                if c == 'a' {
+                    Some(0)
+                } else {
+                    None
+                }
+            })
+        };
+        assert_eq!(TEXT.get_glyph('a').unwrap().advance, 2);
+        assert_eq!(TEXT.get_glyph('*').unwrap().advance, 5);
+        assert_eq!(TEXT.get_glyph('?').unwrap().advance, 3);
+        assert!(SYMBOLS.get_glyph('?').is_none());
+        // No kerning across glyphs from the fallback font.
+        assert_eq!(TEXT.get_text_size("aa*a?").0, 2 + 1 + 5 + 2 + 3);
+    }
+
+    #[test]
+    fn test_glyph_ranges() {
+        const RANGES: Font<MonoBitmapImage> = Font {
+            glyph_index: GlyphIndex::Ranges(&[
+                GlyphRange {
+                    start: 'a' as u32,
+                    len: 3,
+                    index: 0,
+                },
+                GlyphRange {
+                    start: 'x' as u32,
+                    len: 1,
+                    index: 3,
+                },
+                GlyphRange {
+                    start: '\u{4e00}' as u32,
+                    len: 2,
+                    index: 4,
+                },
+            ]),
+            ..test_font(2, 0, &[], |_| None)
+        };
+        let indices = "`abcdwxyz\u{4e00}\u{4e01}\u{4e02}"
+            .chars()
+            .map(|c| RANGES.get_glyph_index(c))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            indices,
+            [
+                None,
+                Some(0),
+                Some(1),
+                Some(2),
+                None,
+                None,
+                Some(3),
+                None,
+                None,
+                Some(4),
+                Some(5),
+                None
+            ]
+        );
+    }
+}
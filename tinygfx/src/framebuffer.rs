@@ -0,0 +1,264 @@
+use core::cmp::{max, min};
+use core::marker::PhantomData;
+
+use super::color::Color;
+use super::{Layout, Renderer};
+
+/// Buffer containing the whole frame, as an alternative to rendering the frame in parts via
+/// `Frame::draw_part()`.
+///
+/// The buffer is never cleared implicitly, so the content can be modified incrementally across
+/// multiple calls to `draw()`. `Buffer` can either be an owned array or a borrowed slice.
+pub struct Framebuffer<Buffer, ColorType> {
+    buffer: Buffer,
+    width: u32,
+    height: u32,
+    layout: Layout,
+    row_alignment: usize,
+    phantom: PhantomData<ColorType>,
+}
+
+impl<Buffer, ColorType> Framebuffer<Buffer, ColorType>
+where
+    Buffer: AsRef<[u8]> + AsMut<[u8]>,
+    ColorType: Color,
+{
+    pub fn new(width: u32, height: u32, buffer: Buffer) -> Self {
+        let framebuffer = Self {
+            buffer,
+            width,
+            height,
+            layout: Layout::Rows,
+            row_alignment: 1,
+            phantom: PhantomData,
+        };
+        framebuffer.check_size();
+        framebuffer
+    }
+
+    pub fn layout(&mut self, layout: Layout) {
+        layout.check_color::<ColorType>();
+        self.layout = layout;
+        self.check_size();
+    }
+
+    /// Pads each row (or page) so that the stride is a multiple of `alignment` bytes.
+    pub fn row_alignment(&mut self, alignment: usize) {
+        assert!(alignment != 0);
+        self.row_alignment = alignment;
+        self.check_size();
+    }
+
+    pub fn stride(&self) -> usize {
+        self.layout
+            .padded_stride::<ColorType>(self.width, self.row_alignment)
+    }
+
+    /// Returns a renderer which covers the whole frame.
+    pub fn renderer(&mut self) -> Renderer<'_, ColorType> {
+        let stride = self.stride();
+        Renderer {
+            buffer: self.buffer.as_mut(),
+            width: self.width,
+            height: self.height,
+            current_top: 0,
+            current_bottom: self.height as i32,
+            mirror_y: false,
+            layout: self.layout,
+            stride,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn draw<Draw>(&mut self, draw: Draw)
+    where
+        Draw: FnOnce(Renderer<ColorType>),
+    {
+        draw(self.renderer());
+    }
+
+    pub fn buffer(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut [u8] {
+        self.buffer.as_mut()
+    }
+
+    pub fn into_inner(self) -> Buffer {
+        self.buffer
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Replaces the color of the area of like-colored pixels around (`x`, `y`) with `color`.
+    ///
+    /// `stack` holds the positions which still have to be scanned. Its size limits the
+    /// complexity of the filled area. If the stack overflows, the area is only filled partially
+    /// and an error is returned.
+    pub fn flood_fill(
+        &mut self,
+        x: i32,
+        y: i32,
+        color: ColorType,
+        connectivity: Connectivity,
+        stack: &mut [(i32, i32)],
+    ) -> Result<(), FloodFillError>
+    where
+        ColorType: PartialEq,
+    {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let mut renderer = self.renderer();
+        let clip = renderer.full_frame();
+        let target = match renderer.get_pixel(x, y) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        if target == color {
+            return Ok(());
+        }
+        if stack.is_empty() {
+            return Err(FloodFillError::StackOverflow);
+        }
+        let is_target = |renderer: &Renderer<ColorType>, x: i32, y: i32| {
+            renderer.get_pixel(x, y) == Some(target)
+        };
+
+        stack[0] = (x, y);
+        let mut stack_size = 1;
+        while stack_size != 0 {
+            stack_size -= 1;
+            let (x, y) = stack[stack_size];
+            if !is_target(&renderer, x, y) {
+                continue;
+            }
+            // Fill the whole span containing the pixel.
+            let mut left = x;
+            while is_target(&renderer, left - 1, y) {
+                left -= 1;
+            }
+            let mut right = x + 1;
+            while is_target(&renderer, right, y) {
+                right += 1;
+            }
+            renderer.fill(clip, left, y, right, y + 1, color);
+
+            // Push one position for each adjacent span in the rows above and below.
+            let (scan_left, scan_right) = match connectivity {
+                Connectivity::Four => (left, right),
+                Connectivity::Eight => (max(left - 1, 0), min(right + 1, width)),
+            };
+            for &scan_y in &[y - 1, y + 1] {
+                if scan_y < 0 || scan_y >= height {
+                    continue;
+                }
+                let mut in_span = false;
+                for scan_x in scan_left..scan_right {
+                    if !is_target(&renderer, scan_x, scan_y) {
+                        in_span = false;
+                    } else if !in_span {
+                        if stack_size == stack.len() {
+                            return Err(FloodFillError::StackOverflow);
+                        }
+                        stack[stack_size] = (scan_x, scan_y);
+                        stack_size += 1;
+                        in_span = true;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_size(&self) {
+        let strides = (self.height as usize).div_ceil(self.layout.rows_per_stride());
+        assert!(self.buffer.as_ref().len() >= strides * self.stride());
+    }
+}
+
+/// Pixels which are considered adjacent by `Framebuffer::flood_fill()`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Connectivity {
+    /// Only horizontally and vertically adjacent pixels.
+    Four,
+    /// Horizontally, vertically and diagonally adjacent pixels.
+    Eight,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FloodFillError {
+    /// The stack passed to `Framebuffer::flood_fill()` was too small for the area.
+    StackOverflow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color::BlackWhite::{Black, White};
+    use super::super::Layout;
+    use super::{Connectivity, FloodFillError, Framebuffer};
+
+    #[test]
+    fn test_incremental_drawing() {
+        let mut framebuffer = Framebuffer::new(16, 2, [0u8; 4]);
+        framebuffer.draw(|mut renderer| {
+            renderer.clear(White);
+        });
+        framebuffer.draw(|mut renderer| {
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 4, 1, 12, 2, Black);
+        });
+        assert_eq!(framebuffer.buffer(), &[0xff, 0xff, 0xf0, 0x0f]);
+
+        let mut buffer = [0u8; 16];
+        let mut framebuffer = Framebuffer::new(8, 16, &mut buffer[..]);
+        framebuffer.layout(Layout::Pages);
+        framebuffer.draw(|mut renderer| {
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 0, 7, 1, 9, White);
+        });
+        let mut renderer = framebuffer.renderer();
+        let clip = renderer.full_frame();
+        renderer.fill(clip, 7, 15, 8, 16, White);
+        assert_eq!(buffer[0], 0x80);
+        assert_eq!(buffer[8], 0x01);
+        assert_eq!(buffer[15], 0x80);
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        // Two areas which are only connected diagonally.
+        let image = [0x0f, 0x0f, 0x0f, 0xf0, 0xf0, 0xf0];
+        let mut framebuffer = Framebuffer::new(8, 6, image);
+        let mut stack = [(0, 0); 8];
+        framebuffer
+            .flood_fill(0, 0, White, Connectivity::Four, &mut stack)
+            .unwrap();
+        assert_eq!(framebuffer.buffer(), &[0xff, 0xff, 0xff, 0xf0, 0xf0, 0xf0]);
+
+        let mut framebuffer = Framebuffer::new(8, 6, image);
+        framebuffer
+            .flood_fill(1, 1, White, Connectivity::Eight, &mut stack)
+            .unwrap();
+        assert_eq!(framebuffer.buffer(), &[0xff; 6]);
+
+        let mut framebuffer = Framebuffer::new(8, 6, image);
+        framebuffer
+            .flood_fill(4, 0, Black, Connectivity::Four, &mut stack)
+            .unwrap();
+        assert_eq!(framebuffer.buffer(), &[0x00, 0x00, 0x00, 0xf0, 0xf0, 0xf0]);
+
+        let mut framebuffer = Framebuffer::new(8, 6, [0u8; 6]);
+        let mut stack = [(0, 0); 1];
+        assert_eq!(
+            framebuffer.flood_fill(3, 3, White, Connectivity::Four, &mut stack),
+            Err(FloodFillError::StackOverflow)
+        );
+    }
+}
@@ -1,5 +1,9 @@
+use core::cmp::{max, min};
+use core::ops::Range;
+
+use super::color::BlackWhite::{self, White};
 use super::color::Color;
-use super::{Clip, Renderer};
+use super::{Clip, Frame, Renderer};
 
 pub trait MonoImageData {
     fn render_transparent<ColorType: Color>(
@@ -13,6 +17,26 @@ pub trait MonoImageData {
 
     fn width(&self) -> u32;
     fn height(&self) -> u32;
+    /// Returns whether the pixel at the specified position is set.
+    ///
+    /// The default implementation renders the image into a frame covering only that pixel,
+    /// which is slow. Implementations should look up the pixel directly.
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        let mut buffer = [0u8];
+        Frame::new(1, 1, |mut renderer: Renderer<BlackWhite>| {
+            let clip = renderer.full_frame();
+            self.render_transparent(&mut renderer, clip, -(x as i32), -(y as i32), White);
+        })
+        .draw_part(0, &mut buffer);
+        buffer[0] & 0x80 != 0
+    }
+}
+
+/// Returns `len` elements of `data` starting at `offset`.
+///
+/// Used by generated fonts, where the images of all glyphs share one array.
+pub const fn shared_data<T>(data: &'static [T], offset: usize, len: usize) -> &'static [T] {
+    data.split_at(offset).1.split_at(len).0
 }
 
 pub struct MonoBitmapImage {
@@ -38,8 +62,7 @@ impl MonoImageData for MonoBitmapImage {
             if row + y >= clip.bottom() {
                 break;
             }
-            let row_index = (row as i32 * self.stride as i32) as usize;
-            // TODO: Not transparent.
+            let row_index = (row * self.stride as i32) as usize;
             renderer.render_bitmap_row(
                 clip,
                 y + row,
@@ -57,6 +80,11 @@ impl MonoImageData for MonoBitmapImage {
     fn height(&self) -> u32 {
         self.height as u32
     }
+
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        let index = y as usize * self.stride as usize + (x as usize >> 3);
+        self.data[index] & (0x80 >> (x & 7)) != 0
+    }
 }
 
 pub struct MonoRLEImage {
@@ -96,4 +124,359 @@ impl MonoImageData for MonoRLEImage {
     fn height(&self) -> u32 {
         self.height as u32
     }
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        let line_start = self.data[y as usize] as usize;
+        let line_end = self.data[y as usize + 1] as usize;
+        let mut pos = 0;
+        for run in &self.data[line_start..line_end] {
+            pos += (run & 0x7fff) as u32;
+            if pos > x {
+                return (run >> 15) != 0;
+            }
+        }
+        false
+    }
+}
+
+/// Monochrome image which stores the length of each run of pixels in one nibble.
+///
+/// The runs cover all rows in sequence and alternate between unset and set pixels, starting
+/// with unset pixels. Runs longer than 15 pixels are split by empty runs of the other color,
+/// and all pixels after the last run are unset.
+pub struct MonoNibbleRLEImage {
+    pub data: &'static [u8],
+    pub width: u16,
+    pub height: u16,
+}
+
+impl MonoNibbleRLEImage {
+    /// Returns the runs of set pixels, with the pixels numbered row by row.
+    fn set_runs(&self) -> impl Iterator<Item = Range<u32>> + '_ {
+        let mut position = 0;
+        self.data
+            .iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .enumerate()
+            .filter_map(move |(i, length)| {
+                let start = position;
+                position += length as u32;
+                if i & 1 == 1 && length != 0 {
+                    Some(start..position)
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+impl MonoImageData for MonoNibbleRLEImage {
+    fn render_transparent<ColorType: Color>(
+        &self,
+        renderer: &mut Renderer<ColorType>,
+        clip: Clip,
+        x: i32,
+        y: i32,
+        color: ColorType,
+    ) {
+        let width = self.width as u32;
+        for run in self.set_runs() {
+            // Runs can continue in the following rows.
+            let mut start = run.start;
+            while start < run.end {
+                let row = start / width;
+                let end = min(run.end, (row + 1) * width);
+                let row_y = y + row as i32;
+                if row_y >= clip.bottom() {
+                    return;
+                }
+                let left = x + (start % width) as i32;
+                renderer.fill(
+                    clip,
+                    left,
+                    row_y,
+                    left + (end - start) as i32,
+                    row_y + 1,
+                    color,
+                );
+                start = end;
+            }
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.width as u32
+    }
+    fn height(&self) -> u32 {
+        self.height as u32
+    }
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        let index = y * self.width as u32 + x;
+        self.set_runs()
+            .take_while(|run| run.start <= index)
+            .any(|run| run.contains(&index))
+    }
+}
+
+/// Monochrome image in any of the monochrome encodings, so that each glyph of a font can use
+/// the smallest encoding.
+pub enum MonoGlyphImage {
+    Bitmap(MonoBitmapImage),
+    RLE(MonoRLEImage),
+    NibbleRLE(MonoNibbleRLEImage),
+}
+
+impl MonoImageData for MonoGlyphImage {
+    fn render_transparent<ColorType: Color>(
+        &self,
+        renderer: &mut Renderer<ColorType>,
+        clip: Clip,
+        x: i32,
+        y: i32,
+        color: ColorType,
+    ) {
+        match self {
+            Self::Bitmap(image) => image.render_transparent(renderer, clip, x, y, color),
+            Self::RLE(image) => image.render_transparent(renderer, clip, x, y, color),
+            Self::NibbleRLE(image) => image.render_transparent(renderer, clip, x, y, color),
+        }
+    }
+
+    fn width(&self) -> u32 {
+        match self {
+            Self::Bitmap(image) => image.width(),
+            Self::RLE(image) => image.width(),
+            Self::NibbleRLE(image) => image.width(),
+        }
+    }
+    fn height(&self) -> u32 {
+        match self {
+            Self::Bitmap(image) => image.height(),
+            Self::RLE(image) => image.height(),
+            Self::NibbleRLE(image) => image.height(),
+        }
+    }
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        match self {
+            Self::Bitmap(image) => image.get_pixel(x, y),
+            Self::RLE(image) => image.get_pixel(x, y),
+            Self::NibbleRLE(image) => image.get_pixel(x, y),
+        }
+    }
+}
+
+/// Image with 2 or 4 bits of coverage per pixel, used for anti-aliased glyphs.
+///
+/// The coverage is blended into the destination via `Color::mix()`.
+pub struct GrayBitmapImage {
+    pub data: &'static [u8],
+    pub width: u16,
+    pub height: u16,
+    pub stride: u16,
+    pub bits_per_pixel: u8,
+}
+
+impl GrayBitmapImage {
+    /// Returns the coverage of the pixel, scaled to the range from 0 to 255.
+    pub fn get_coverage(&self, x: u32, y: u32) -> u8 {
+        let bits_per_pixel = self.bits_per_pixel as u32;
+        let bit = x * bits_per_pixel;
+        let index = y as usize * self.stride as usize + (bit >> 3) as usize;
+        let max = (1u32 << bits_per_pixel) - 1;
+        let value = (self.data[index] as u32 >> (8 - bits_per_pixel - (bit & 7))) & max;
+        (value * 255 / max) as u8
+    }
+}
+
+impl MonoImageData for GrayBitmapImage {
+    fn render_transparent<ColorType: Color>(
+        &self,
+        renderer: &mut Renderer<ColorType>,
+        clip: Clip,
+        x: i32,
+        y: i32,
+        color: ColorType,
+    ) {
+        let left = max(clip.left() - x, 0);
+        let right = min(clip.right() - x, self.width as i32);
+        for row in 0..self.height as i32 {
+            if row + y < clip.top() {
+                continue;
+            }
+            if row + y >= clip.bottom() {
+                break;
+            }
+            for column in left..right {
+                let coverage = self.get_coverage(column as u32, row as u32);
+                renderer.blend_pixel(clip, x + column, y + row, color, coverage);
+            }
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.width as u32
+    }
+    fn height(&self) -> u32 {
+        self.height as u32
+    }
+    fn get_pixel(&self, x: u32, y: u32) -> bool {
+        self.get_coverage(x, y) >= 128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::color::BlackWhite::{self, Black, White};
+    use super::super::color::{Color, Gray4};
+    use super::super::{Clip, Frame, Renderer};
+    use super::{
+        shared_data, GrayBitmapImage, MonoBitmapImage, MonoGlyphImage, MonoImageData,
+        MonoNibbleRLEImage, MonoRLEImage,
+    };
+
+    #[test]
+    fn test_nibble_rle_image() {
+        // Two rows: "X.X" and ".XX".
+        let image = MonoNibbleRLEImage {
+            data: &[0x01, 0x11, 0x12],
+            width: 3,
+            height: 2,
+        };
+        assert!(image.get_pixel(0, 0));
+        assert!(!image.get_pixel(1, 0));
+        assert!(!image.get_pixel(0, 1));
+        assert!(image.get_pixel(2, 1));
+
+        let frame = Frame::new(8, 3, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            image.render_transparent(&mut renderer, clip, 4, 1, White);
+        });
+        let mut buffer = [0u8; 3];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x00, 0x0a, 0x06]);
+
+        // A run of 20 pixels which continues in the second row.
+        let image = MonoNibbleRLEImage {
+            data: &[0x2f, 0x05],
+            width: 12,
+            height: 2,
+        };
+        assert!(!image.get_pixel(1, 0));
+        assert!(image.get_pixel(2, 0));
+        assert!(image.get_pixel(9, 1));
+        assert!(!image.get_pixel(10, 1));
+        let frame = Frame::new(16, 2, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            image.render_transparent(&mut renderer, clip, 0, 0, White);
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x3f, 0xf0, 0xff, 0xc0]);
+    }
+
+    #[test]
+    fn test_gray_image() {
+        let image = GrayBitmapImage {
+            data: &[0b11_10_01_00, 0b01_00_00_00],
+            width: 4,
+            height: 2,
+            stride: 1,
+            bits_per_pixel: 2,
+        };
+        assert_eq!(image.get_coverage(1, 0), 170);
+        assert!(image.get_pixel(1, 0));
+        assert!(!image.get_pixel(2, 0));
+
+        let frame = Frame::new(4, 2, |mut renderer: Renderer<Gray4>| {
+            renderer.clear(Gray4(3));
+            let clip = renderer.full_frame();
+            image.render_transparent(&mut renderer, clip, 1, 0, Gray4(15));
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x3f, 0xb7, 0x37, 0x33]);
+    }
+
+    #[test]
+    fn test_glyph_image() {
+        // The same 2x2 image with the diagonal set, in each encoding.
+        let images = [
+            MonoGlyphImage::Bitmap(MonoBitmapImage {
+                data: &[0x80, 0x40],
+                width: 2,
+                height: 2,
+                stride: 1,
+            }),
+            MonoGlyphImage::RLE(MonoRLEImage {
+                data: &[3, 5, 7, 0x8001, 0x0001, 0x0001, 0x8001],
+                width: 2,
+                height: 2,
+            }),
+            MonoGlyphImage::NibbleRLE(MonoNibbleRLEImage {
+                data: &[0x01, 0x21],
+                width: 2,
+                height: 2,
+            }),
+        ];
+        for image in images.iter() {
+            assert_eq!((image.width(), image.height()), (2, 2));
+            assert!(image.get_pixel(1, 1));
+            assert!(!image.get_pixel(1, 0));
+            let frame = Frame::new(8, 2, |mut renderer: Renderer<BlackWhite>| {
+                renderer.clear(Black);
+                let clip = renderer.full_frame();
+                image.render_transparent(&mut renderer, clip, 1, 0, White);
+            });
+            let mut buffer = [0u8; 2];
+            frame.draw_part(0, &mut buffer);
+            assert_eq!(buffer, [0x40, 0x20]);
+        }
+    }
+
+    #[test]
+    fn test_shared_data() {
+        static DATA: [u8; 4] = [1, 2, 3, 4];
+        const SLICE: &[u8] = shared_data(&DATA, 1, 2);
+        assert_eq!(SLICE, &[2, 3]);
+        assert!(shared_data(&DATA, 4, 0).is_empty());
+    }
+
+    #[test]
+    fn test_default_get_pixel() {
+        // Only implements the required methods.
+        struct Image(MonoBitmapImage);
+
+        impl MonoImageData for Image {
+            fn render_transparent<ColorType: Color>(
+                &self,
+                renderer: &mut Renderer<ColorType>,
+                clip: Clip,
+                x: i32,
+                y: i32,
+                color: ColorType,
+            ) {
+                self.0.render_transparent(renderer, clip, x, y, color);
+            }
+            fn width(&self) -> u32 {
+                self.0.width()
+            }
+            fn height(&self) -> u32 {
+                self.0.height()
+            }
+        }
+
+        let image = Image(MonoBitmapImage {
+            data: &[0x81, 0x80, 0x40, 0x00],
+            width: 10,
+            height: 2,
+            stride: 2,
+        });
+        let pixels = [(0, 0), (7, 0), (8, 0), (1, 1)];
+        for y in 0..2 {
+            for x in 0..10 {
+                assert_eq!(image.get_pixel(x, y), pixels.contains(&(x, y)));
+            }
+        }
+    }
 }
@@ -0,0 +1,401 @@
+//! Streaming of frames to display controllers via `display-interface`.
+//!
+//! Any `WriteOnlyDataCommand` can be used, e.g., `display_interface_spi::SPIInterface` for
+//! controllers connected via embedded-hal SPI and a D/C pin.
+
+use core::cmp::min;
+#[cfg(feature = "async")]
+use core::future::{poll_fn, Future};
+#[cfg(feature = "async")]
+use core::mem::swap;
+#[cfg(feature = "async")]
+use core::pin::pin;
+#[cfg(feature = "async")]
+use core::task::Poll;
+
+#[cfg(feature = "async")]
+use display_interface::AsyncWriteOnlyDataCommand;
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use super::color::Color;
+use super::{DirtyRect, Frame, Layout, Renderer};
+
+/// Renders the whole frame part by part into `buffer` and sends each part as data.
+///
+/// The buffer needs to be large enough for at least one row (or page for `Layout::Pages`).
+/// Any command required to start the RAM transfer has to be sent beforehand.
+pub fn send_frame<Draw, ColorType, Interface>(
+    frame: &Frame<Draw, ColorType>,
+    interface: &mut Interface,
+    buffer: &mut [u8],
+) -> Result<(), DisplayError>
+where
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+    Interface: WriteOnlyDataCommand,
+{
+    send_rows(frame, interface, buffer, 0, frame.height())
+}
+
+/// Renders the rows from `top` to `bottom` part by part into `buffer` and sends each part as
+/// data.
+///
+/// For `Layout::Pages`, `top` and `bottom` are rounded to whole pages.
+pub fn send_rows<Draw, ColorType, Interface>(
+    frame: &Frame<Draw, ColorType>,
+    interface: &mut Interface,
+    buffer: &mut [u8],
+    top: u32,
+    bottom: u32,
+) -> Result<(), DisplayError>
+where
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+    Interface: WriteOnlyDataCommand,
+{
+    let window = DirtyRect {
+        left: 0,
+        top,
+        right: frame.width(),
+        bottom,
+    };
+    send_window(frame, interface, buffer, window)
+}
+
+/// Renders the rows of `window` part by part into `buffer` and sends the pixels within the
+/// window as data, without any padding between the rows.
+///
+/// The window is specified in buffer coordinates (i.e., after mirroring) and has to start at a
+/// byte boundary. For `Layout::Pages`, the window is extended to whole pages.
+pub fn send_window<Draw, ColorType, Interface>(
+    frame: &Frame<Draw, ColorType>,
+    interface: &mut Interface,
+    buffer: &mut [u8],
+    window: DirtyRect,
+) -> Result<(), DisplayError>
+where
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+    Interface: WriteOnlyDataCommand,
+{
+    let mut parts = Parts::new(frame, window, buffer.len());
+    while let Some((y, strides)) = parts.next() {
+        let length = parts.render(frame, buffer, y, strides);
+        interface.send_data(DataFormat::U8(&buffer[..length]))?;
+    }
+    Ok(())
+}
+
+/// Renders the whole frame like `send_frame()`, but alternates between two buffers so that the
+/// next part is rendered while the previous part is still being transferred (e.g., via DMA).
+#[cfg(feature = "async")]
+pub async fn send_frame_async<Draw, ColorType, Interface>(
+    frame: &Frame<Draw, ColorType>,
+    interface: &mut Interface,
+    front: &mut [u8],
+    back: &mut [u8],
+) -> Result<(), DisplayError>
+where
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+    Interface: AsyncWriteOnlyDataCommand,
+{
+    let window = DirtyRect {
+        left: 0,
+        top: 0,
+        right: frame.width(),
+        bottom: frame.height(),
+    };
+    send_window_async(frame, interface, front, back, window).await
+}
+
+/// Renders and sends a window like `send_window()`, but alternates between two buffers so that
+/// the next part is rendered while the previous part is still being transferred.
+///
+/// Both buffers should have the same size.
+#[cfg(feature = "async")]
+pub async fn send_window_async<Draw, ColorType, Interface>(
+    frame: &Frame<Draw, ColorType>,
+    interface: &mut Interface,
+    mut front: &mut [u8],
+    mut back: &mut [u8],
+    window: DirtyRect,
+) -> Result<(), DisplayError>
+where
+    Draw: Fn(Renderer<ColorType>),
+    ColorType: Color,
+    Interface: AsyncWriteOnlyDataCommand,
+{
+    let mut parts = Parts::new(frame, window, min(front.len(), back.len()));
+    let (y, strides) = match parts.next() {
+        Some(part) => part,
+        None => return Ok(()),
+    };
+    let mut length = parts.render(frame, front, y, strides);
+    loop {
+        let next = parts.next();
+        let next_length = {
+            let mut transfer = pin!(interface.send_data(DataFormat::U8(&front[..length])));
+            // Start the transfer, then render the next part while the transfer is running.
+            let result = poll_fn(|cx| Poll::Ready(transfer.as_mut().poll(cx))).await;
+            let next_length = next.map(|(y, strides)| parts.render(frame, back, y, strides));
+            match result {
+                Poll::Ready(result) => result?,
+                Poll::Pending => transfer.await?,
+            }
+            next_length
+        };
+        length = match next_length {
+            Some(next_length) => next_length,
+            None => return Ok(()),
+        };
+        swap(&mut front, &mut back);
+    }
+}
+
+/// Splits a window into parts which fit into the buffers.
+struct Parts {
+    stride: usize,
+    rows_per_stride: u32,
+    buffer_strides: usize,
+    first_byte: usize,
+    window_bytes: usize,
+    y: u32,
+    bottom: u32,
+}
+
+impl Parts {
+    fn new<Draw, ColorType>(
+        frame: &Frame<Draw, ColorType>,
+        window: DirtyRect,
+        buffer_size: usize,
+    ) -> Self
+    where
+        Draw: Fn(Renderer<ColorType>),
+        ColorType: Color,
+    {
+        let stride = frame.stride();
+        let rows_per_stride = frame.layout.rows_per_stride() as u32;
+        let buffer_strides = buffer_size / stride;
+        assert!(buffer_strides != 0);
+
+        let (first_byte, last_byte) = match frame.layout {
+            Layout::Rows => {
+                let bits_per_pixel = ColorType::bits_per_pixel();
+                assert_eq!((window.left as usize * bits_per_pixel) & 7, 0);
+                (
+                    (window.left as usize * bits_per_pixel) >> 3,
+                    (window.right as usize * bits_per_pixel).div_ceil(8),
+                )
+            }
+            Layout::Pages => (window.left as usize, window.right as usize),
+        };
+        Self {
+            stride,
+            rows_per_stride,
+            buffer_strides,
+            first_byte,
+            window_bytes: last_byte - first_byte,
+            y: window.top / rows_per_stride * rows_per_stride,
+            bottom: min(window.bottom, frame.height()),
+        }
+    }
+
+    /// Returns the first row and the number of strides of the next part.
+    fn next(&mut self) -> Option<(u32, usize)> {
+        if self.y >= self.bottom {
+            return None;
+        }
+        let y = self.y;
+        let strides = min(
+            self.buffer_strides,
+            (self.bottom - y).div_ceil(self.rows_per_stride) as usize,
+        );
+        self.y += strides as u32 * self.rows_per_stride;
+        Some((y, strides))
+    }
+
+    /// Renders a part and returns the number of bytes to be sent from the start of the buffer.
+    fn render<Draw, ColorType>(
+        &self,
+        frame: &Frame<Draw, ColorType>,
+        buffer: &mut [u8],
+        y: u32,
+        strides: usize,
+    ) -> usize
+    where
+        Draw: Fn(Renderer<ColorType>),
+        ColorType: Color,
+    {
+        frame.draw_part(y, &mut buffer[..strides * self.stride]);
+        if self.window_bytes != self.stride {
+            // Remove everything outside of the window.
+            for i in 0..strides {
+                let start = i * self.stride + self.first_byte;
+                buffer.copy_within(start..start + self.window_bytes, i * self.window_bytes);
+            }
+        }
+        strides * self.window_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+
+    use super::super::color::BlackWhite::{Black, White};
+    use super::super::mock::{RecordingInterface, Transfer};
+    use super::super::{DirtyRect, Frame, Layout};
+    use super::{send_frame, send_rows, send_window};
+
+    #[test]
+    fn test_send_frame() {
+        let mut frame = Frame::new(16, 5, |mut renderer| {
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 0, 1, 4, 4, Black);
+        });
+        let mut interface = RecordingInterface::new();
+        let mut buffer = [0u8; 5];
+        send_frame(&frame, &mut interface, &mut buffer).unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![
+                Transfer::Data(vec![0xff, 0xff, 0x0f, 0xff]),
+                Transfer::Data(vec![0x0f, 0xff, 0x0f, 0xff]),
+                Transfer::Data(vec![0xff, 0xff]),
+            ]
+        );
+
+        let mut interface = RecordingInterface::new();
+        send_rows(&frame, &mut interface, &mut buffer, 3, 4).unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![Transfer::Data(vec![0x0f, 0xff])]
+        );
+
+        frame.layout(Layout::Pages);
+        let mut interface = RecordingInterface::new();
+        let mut buffer = [0u8; 32];
+        send_frame(&frame, &mut interface, &mut buffer).unwrap();
+        // Only the first five rows of the page are part of the frame.
+        let mut expected = vec![0x1f; 16];
+        for byte in &mut expected[..4] {
+            *byte = 0x11;
+        }
+        assert_eq!(interface.transfers(), vec![Transfer::Data(expected)]);
+    }
+
+    #[test]
+    fn test_send_window() {
+        let mut frame = Frame::new(24, 4, |mut renderer| {
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 8, 1, 12, 3, Black);
+        });
+        frame.row_alignment(4);
+        let window = DirtyRect {
+            left: 8,
+            top: 1,
+            right: 20,
+            bottom: 3,
+        };
+        let mut interface = RecordingInterface::new();
+        let mut buffer = [0u8; 8];
+        send_window(&frame, &mut interface, &mut buffer, window).unwrap();
+        assert_eq!(
+            interface.transfers(),
+            vec![Transfer::Data(vec![0x0f, 0xff, 0x0f, 0xff])]
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_send_frame_async() {
+        use core::cell::Cell;
+        use core::future::Future;
+        use core::pin::pin;
+        use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+        use display_interface::{AsyncWriteOnlyDataCommand, DataFormat, DisplayError};
+        use std::vec::Vec;
+
+        use super::send_frame_async;
+
+        /// Transport which needs two polls for each transfer and records the number of parts
+        /// which were rendered at the end of each transfer.
+        struct FakeTransport<'a> {
+            rendered: &'a Cell<u32>,
+            transfers: Vec<(Vec<u8>, u32)>,
+        }
+
+        impl<'a> AsyncWriteOnlyDataCommand for FakeTransport<'a> {
+            async fn send_commands(&mut self, _cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+                Ok(())
+            }
+
+            async fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+                let data = match buf {
+                    DataFormat::U8(data) => data.to_vec(),
+                    _ => return Err(DisplayError::DataFormatNotImplemented),
+                };
+                let mut polled = false;
+                core::future::poll_fn(|cx| {
+                    if polled {
+                        Poll::Ready(())
+                    } else {
+                        polled = true;
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                })
+                .await;
+                self.transfers.push((data, self.rendered.get()));
+                Ok(())
+            }
+        }
+
+        /// Waker which does nothing, as the future is polled in a loop.
+        fn noop_waker() -> Waker {
+            fn clone(_: *const ()) -> RawWaker {
+                RawWaker::new(core::ptr::null(), &VTABLE)
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            // The functions of the vtable ignore the data pointer.
+            unsafe { Waker::from_raw(clone(core::ptr::null())) }
+        }
+
+        let rendered = Cell::new(0);
+        let frame = Frame::new(8, 3, |mut renderer| {
+            let y = renderer.current_top_row();
+            renderer.clear(White);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 0, y, y + 1, y + 1, Black);
+            rendered.set(rendered.get() + 1);
+        });
+        let mut transport = FakeTransport {
+            rendered: &rendered,
+            transfers: Vec::new(),
+        };
+        let mut front = [0u8; 1];
+        let mut back = [0u8; 1];
+        {
+            let mut future = pin!(send_frame_async(
+                &frame,
+                &mut transport,
+                &mut front,
+                &mut back
+            ));
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            while future.as_mut().poll(&mut cx).is_pending() {}
+        }
+        // The next part was always rendered before the previous transfer was finished.
+        assert_eq!(
+            transport.transfers,
+            vec![(vec![0x7f], 2), (vec![0x3f], 3), (vec![0x1f], 3)]
+        );
+    }
+}
@@ -1,14 +1,25 @@
 #![no_std]
 
 pub mod color;
+pub mod diff;
+#[cfg(feature = "embedded-graphics")]
+pub mod eg;
+#[cfg(feature = "display-interface")]
+pub mod epd;
 pub mod font;
+pub mod framebuffer;
 pub mod image;
+#[cfg(feature = "display-interface")]
+pub mod interface;
+#[cfg(any(feature = "mock", all(test, feature = "display-interface")))]
+pub mod mock;
 
 use core::cmp::max;
 use core::cmp::min;
 use core::marker::PhantomData;
 
 use color::Color;
+use diff::Diff;
 use font::Font;
 use image::MonoImageData;
 
@@ -19,6 +30,8 @@ pub struct Renderer<'a, ColorType> {
     current_top: i32,
     current_bottom: i32,
     mirror_y: bool,
+    layout: Layout,
+    stride: usize,
     phantom: PhantomData<ColorType>,
 }
 
@@ -39,11 +52,40 @@ where
             .clip(left, top, right, bottom)
             .clip_top(self.current_top)
             .clip_bottom(self.current_bottom);
-        if clip.is_empty() {
+        if clip.is_empty() || clip.top() >= clip.bottom() {
             return;
         }
-        for y in clip.top()..clip.bottom() {
-            color.fill(self.row_buffer(y), clip.left(), clip.right());
+        match self.layout {
+            Layout::Rows => {
+                for y in clip.top()..clip.bottom() {
+                    color.fill(self.row_buffer(y), clip.left(), clip.right());
+                }
+            }
+            Layout::Pages => {
+                // Convert the rows into the range of rows within the buffer, which is a single
+                // contiguous range even if the buffer is mirrored.
+                let (first, last) = if self.mirror_y {
+                    (
+                        self.local_row(clip.bottom() - 1),
+                        self.local_row(clip.top()),
+                    )
+                } else {
+                    (
+                        self.local_row(clip.top()),
+                        self.local_row(clip.bottom() - 1),
+                    )
+                };
+                for page in (first >> 3)..=(last >> 3) {
+                    let start = max(first, page << 3) & 7;
+                    let end = min(last, (page << 3) + 7) & 7;
+                    let mask = (0xffu8 << start) & (0xffu8 >> (7 - end));
+                    let page_buffer = self.page_buffer(page);
+                    color.fill_page(
+                        &mut page_buffer[clip.left() as usize..clip.right() as usize],
+                        mask,
+                    );
+                }
+            }
         }
     }
 
@@ -54,13 +96,12 @@ where
         if !clip.contains_row(y) {
             return;
         }
-        let row_buffer = self.row_buffer(y);
         let mut pos = x;
         for run in data {
             let length = (run & 0x7fff) as u32;
             if (run >> 15) != 0u16 {
                 let run_clip = clip.clip_left(pos).clip_right(pos + length as i32);
-                color.fill(row_buffer, run_clip.left(), run_clip.right());
+                self.fill_row(y, run_clip.left(), run_clip.right(), color);
             }
             pos += length as i32;
         }
@@ -75,20 +116,95 @@ where
         bits: &[u8],
         color: ColorType,
     ) {
-        // TODO: Color!
         let clip = clip.clip(left, self.current_top, right, self.current_bottom);
         if clip.is_empty() || !clip.contains_row(y) {
             return;
         }
 
-        let row_buffer = self.row_buffer(y);
-        color.render_bitmap_row(
-            row_buffer,
-            left,
-            bits,
-            clip.left() - left,
-            clip.right() - right,
-        );
+        match self.layout {
+            Layout::Rows => {
+                let row_buffer = self.row_buffer(y);
+                color.render_bitmap_row(
+                    row_buffer,
+                    left,
+                    bits,
+                    clip.left() - left,
+                    clip.right() - left,
+                );
+            }
+            Layout::Pages => {
+                let row = self.local_row(y);
+                let mask = 1u8 << (row & 7);
+                let page_buffer = self.page_buffer(row >> 3);
+                let is_set = |x: i32| {
+                    let bit = (x - left) as usize;
+                    bits[bit >> 3] & (0x80 >> (bit & 7)) != 0
+                };
+                // Fill runs of set pixels at once and skip empty bytes of the bitmap.
+                let mut x = clip.left();
+                while x < clip.right() {
+                    let bit = x - left;
+                    if bit & 7 == 0 && bits[bit as usize >> 3] == 0 {
+                        x += 8;
+                    } else if !is_set(x) {
+                        x += 1;
+                    } else {
+                        let start = x;
+                        while x < clip.right() && is_set(x) {
+                            x += 1;
+                        }
+                        color.fill_page(&mut page_buffer[start as usize..x as usize], mask);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Mixes `color` into the pixel at (`x`, `y`), where `alpha` (0 to 255) is the weight of
+    /// `color`.
+    pub fn blend_pixel(&mut self, clip: Clip, x: i32, y: i32, color: ColorType, alpha: u8) {
+        match alpha {
+            0 => {}
+            255 => self.set_pixel(clip, x, y, color),
+            _ => {
+                if let Some(background) = self.get_pixel(x, y) {
+                    self.set_pixel(clip, x, y, color.mix(background, alpha));
+                }
+            }
+        }
+    }
+
+    pub fn set_pixel(&mut self, clip: Clip, x: i32, y: i32, color: ColorType) {
+        let clip = clip
+            .clip_top(self.current_top)
+            .clip_bottom(self.current_bottom);
+        if x < clip.left() || x >= clip.right() || !clip.contains_row(y) {
+            return;
+        }
+        match self.layout {
+            Layout::Rows => color.set(self.row_buffer(y), x),
+            Layout::Pages => {
+                let row = self.local_row(y);
+                let page_buffer = self.page_buffer(row >> 3);
+                color.fill_page(&mut page_buffer[x as usize..x as usize + 1], 1 << (row & 7));
+            }
+        }
+    }
+
+    /// Returns the color of a pixel, or `None` if the pixel is outside of the area covered by
+    /// the renderer.
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<ColorType> {
+        if x < 0 || x >= self.width as i32 || y < self.current_top || y >= self.current_bottom {
+            return None;
+        }
+        let row = self.local_row(y);
+        Some(match self.layout {
+            Layout::Rows => ColorType::get(&self.buffer[row * self.stride..], x),
+            Layout::Pages => {
+                let byte = self.buffer[(row >> 3) * self.stride + x as usize];
+                ColorType::get_page(byte, row & 7)
+            }
+        })
     }
 
     pub fn clear(&mut self, color: ColorType) {
@@ -119,15 +235,111 @@ where
         self.current_bottom
     }
 
-    fn row_buffer(&mut self, row: i32) -> &mut [u8] {
-        let y_offset = if self.mirror_y {
+    fn fill_row(&mut self, row: i32, left: i32, right: i32, color: ColorType) {
+        if left >= right {
+            return;
+        }
+        match self.layout {
+            Layout::Rows => color.fill(self.row_buffer(row), left, right),
+            Layout::Pages => {
+                let row = self.local_row(row);
+                let page_buffer = self.page_buffer(row >> 3);
+                color.fill_page(
+                    &mut page_buffer[left as usize..right as usize],
+                    1 << (row & 7),
+                );
+            }
+        }
+    }
+
+    fn local_row(&self, row: i32) -> usize {
+        (if self.mirror_y {
             self.current_bottom - 1 - row
         } else {
             row - self.current_top
-        } as usize;
-        let stride = (self.width as usize * ColorType::bits_per_pixel() + 7) >> 3;
+        }) as usize
+    }
+
+    fn row_buffer(&mut self, row: i32) -> &mut [u8] {
+        let y_offset = self.local_row(row);
+        let stride = self.stride;
         &mut self.buffer[y_offset * stride..(y_offset + 1) * stride]
     }
+
+    fn page_buffer(&mut self, page: usize) -> &mut [u8] {
+        let stride = self.stride;
+        &mut self.buffer[page * stride..page * stride + self.width as usize]
+    }
+}
+
+/// Organization of the pixels in the buffers passed to `Frame::draw_part`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Layout {
+    /// Each row is stored as a contiguous sequence of bytes, with the leftmost pixel in the most
+    /// significant bits of the first byte.
+    Rows,
+    /// Rows are grouped into pages of eight rows, and each byte contains one column of a page
+    /// with the topmost pixel in the least significant bit, as used by SSD1306 and SH1106 OLED
+    /// controllers. Only available for colors with one bit per pixel.
+    Pages,
+}
+
+impl Layout {
+    /// Returns the number of bytes per row (for `Rows`) or per page (for `Pages`), without any
+    /// padding.
+    pub fn stride<ColorType: Color>(&self, width: u32) -> usize {
+        match self {
+            Layout::Rows => (width as usize * ColorType::bits_per_pixel() + 7) >> 3,
+            Layout::Pages => width as usize,
+        }
+    }
+
+    /// Returns the stride padded to a multiple of `alignment` bytes.
+    pub(crate) fn padded_stride<ColorType: Color>(&self, width: u32, alignment: usize) -> usize {
+        let stride = self.stride::<ColorType>(width);
+        stride.div_ceil(alignment) * alignment
+    }
+
+    /// Panics if pixels of the color cannot be stored in this layout.
+    pub(crate) fn check_color<ColorType: Color>(&self) {
+        if *self == Layout::Pages {
+            assert_eq!(ColorType::bits_per_pixel(), 1);
+        }
+    }
+
+    /// Returns the number of rows stored in one stride.
+    pub fn rows_per_stride(&self) -> usize {
+        match self {
+            Layout::Rows => 1,
+            Layout::Pages => 8,
+        }
+    }
+
+    /// Returns the order in which the pixels are natively stored within each byte.
+    pub fn bit_order(&self) -> BitOrder {
+        match self {
+            Layout::Rows => BitOrder::MsbFirst,
+            Layout::Pages => BitOrder::LsbFirst,
+        }
+    }
+}
+
+/// Order of the pixels within each byte of the buffer.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BitOrder {
+    /// The first pixel (leftmost for `Layout::Rows`, topmost for `Layout::Pages`) is stored in
+    /// the most significant bits.
+    MsbFirst,
+    /// The first pixel is stored in the least significant bits.
+    LsbFirst,
+}
+
+/// Mapping between pixel values and the bits in the buffer.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Polarity {
+    Normal,
+    /// All bits are inverted, e.g., 1 means black for `BlackWhite`.
+    Inverted,
 }
 
 #[derive(Copy, Clone)]
@@ -201,13 +413,7 @@ impl Clip {
     }
 
     pub fn contains_row(self, row: i32) -> bool {
-        if (row) < self.top {
-            false
-        } else if row >= self.bottom {
-            false
-        } else {
-            true
-        }
+        row >= self.top && row < self.bottom
     }
 
     pub fn is_empty(&self) -> bool {
@@ -221,6 +427,10 @@ pub struct Frame<Draw, ColorType> {
     draw: Draw,
     mirror_x: bool,
     mirror_y: bool,
+    layout: Layout,
+    bit_order: Option<BitOrder>,
+    polarity: Polarity,
+    row_alignment: usize,
     phantom: PhantomData<ColorType>,
 }
 
@@ -236,6 +446,10 @@ where
             draw,
             mirror_x: false,
             mirror_y: false,
+            layout: Layout::Rows,
+            bit_order: None,
+            polarity: Polarity::Normal,
+            row_alignment: 1,
             phantom: PhantomData::<ColorType>,
         }
     }
@@ -248,10 +462,43 @@ where
         self.mirror_y = mirror_y;
     }
 
+    pub fn layout(&mut self, layout: Layout) {
+        layout.check_color::<ColorType>();
+        self.layout = layout;
+    }
+
+    /// Overrides the order of the pixels within each byte, which defaults to
+    /// `Layout::bit_order()`.
+    pub fn bit_order(&mut self, bit_order: BitOrder) {
+        self.bit_order = Some(bit_order);
+    }
+
+    pub fn polarity(&mut self, polarity: Polarity) {
+        self.polarity = polarity;
+    }
+
+    /// Pads each row (or page) so that the stride is a multiple of `alignment` bytes.
+    pub fn row_alignment(&mut self, alignment: usize) {
+        assert!(alignment != 0);
+        self.row_alignment = alignment;
+    }
+
+    /// Returns the number of bytes between the starts of two consecutive rows (or pages) in the
+    /// buffers passed to `draw_part()`.
+    pub fn stride(&self) -> usize {
+        self.layout
+            .padded_stride::<ColorType>(self.width, self.row_alignment)
+    }
+
     // TODO: top/bottom instead of y?
     pub fn draw_part(&self, y: u32, buffer: &mut [u8]) {
-        let stride = (self.width as usize * ColorType::bits_per_pixel() + 7) >> 3;
-        let lines = buffer.len() / stride;
+        let stride = self.stride();
+        let strides = buffer.len() / stride;
+        let lines = strides * self.layout.rows_per_stride();
+        if self.layout == Layout::Pages {
+            // Pages can only be rendered as a whole.
+            assert_eq!(y & 7, 0);
+        }
         //assert!(buffer.len() * 8 >= self.width as usize * ColorType::bits_per_pixel()); TODO
         let mut top = y as i32;
         let mut bottom = y as i32 + lines as i32;
@@ -266,16 +513,115 @@ where
             current_top: top,
             current_bottom: bottom,
             mirror_y: self.mirror_y,
+            layout: self.layout,
+            stride,
             phantom: PhantomData,
         });
+        let row_bytes = self.layout.stride::<ColorType>(self.width);
         if self.mirror_x {
-            for i in 0..lines {
-                ColorType::mirror_x(
-                    &mut buffer[i * stride..(i + 1) * stride],
-                    self.width as usize,
-                );
+            for i in 0..strides {
+                let row = &mut buffer[i * stride..i * stride + row_bytes];
+                match self.layout {
+                    Layout::Rows => ColorType::mirror_x(row, self.width as usize),
+                    Layout::Pages => row.reverse(),
+                }
+            }
+        }
+        if self.bit_order.unwrap_or_else(|| self.layout.bit_order()) != self.layout.bit_order() {
+            let bits_per_pixel = ColorType::bits_per_pixel();
+            for byte in &mut buffer[..strides * stride] {
+                *byte = reverse_pixels(*byte, bits_per_pixel);
             }
         }
+        if self.polarity == Polarity::Inverted {
+            for byte in &mut buffer[..strides * stride] {
+                *byte = !*byte;
+            }
+        }
+    }
+
+    /// Renders a part of the frame like `draw_part()` and compares the result against
+    /// `previous`, which contains the same part of the previously displayed frame.
+    ///
+    /// Returns the bounding rectangle of all changed pixels in buffer coordinates (i.e., after
+    /// mirroring), with the horizontal bounds extended to multiples of `x_align` pixels (but not
+    /// beyond the width of the frame). For `Layout::Pages`, the vertical bounds are always
+    /// aligned to pages.
+    ///
+    /// Panics if `x_align` is 0.
+    pub fn draw_part_dirty(
+        &self,
+        y: u32,
+        buffer: &mut [u8],
+        previous: &[u8],
+        x_align: u32,
+    ) -> Option<DirtyRect> {
+        assert!(x_align > 0);
+        self.draw_part(y, buffer);
+
+        let stride = self.stride();
+        let rows_per_stride = self.layout.rows_per_stride() as u32;
+        let mut dirty: Option<DirtyRect> = None;
+        for change in self.diff(y, previous, buffer) {
+            let strides =
+                (change.rows.start - y) / rows_per_stride..(change.rows.end - y) / rows_per_stride;
+            let first = change.bytes.start;
+            let last = change.bytes.end - 1;
+            let (left, right) = match self.layout {
+                Layout::Rows => {
+                    // Combine the changed bits of all rows of the change.
+                    let changed = |byte: usize| {
+                        strides.clone().fold(0, |changed, i| {
+                            let index = i as usize * stride + byte;
+                            changed | (previous[index] ^ buffer[index])
+                        })
+                    };
+                    (
+                        self.first_changed_pixel(first, changed(first)),
+                        self.last_changed_pixel(last, changed(last)) + 1,
+                    )
+                }
+                Layout::Pages => (first as u32, last as u32 + 1),
+            };
+            let rect = DirtyRect {
+                left: left / x_align * x_align,
+                top: change.rows.start,
+                right: min(right.div_ceil(x_align) * x_align, self.width),
+                bottom: change.rows.end,
+            };
+            dirty = Some(match dirty {
+                Some(dirty) => dirty.union(rect),
+                None => rect,
+            });
+        }
+        dirty
+    }
+
+    /// Compares two buffers rendered with `draw_part(y, ...)` and returns the changed rows
+    /// together with the changed bytes within the rows.
+    pub fn diff<'b>(&self, y: u32, old: &'b [u8], new: &'b [u8]) -> Diff<'b> {
+        Diff::new(old, new, self.stride())
+            .row_bytes(self.layout.stride::<ColorType>(self.width))
+            .rows_per_stride(self.layout.rows_per_stride() as u32)
+            .first_row(y)
+    }
+
+    fn first_changed_pixel(&self, byte: usize, changed: u8) -> u32 {
+        let bits_per_pixel = ColorType::bits_per_pixel() as u32;
+        let offset = match self.bit_order.unwrap_or_else(|| self.layout.bit_order()) {
+            BitOrder::MsbFirst => changed.leading_zeros(),
+            BitOrder::LsbFirst => changed.trailing_zeros(),
+        };
+        (byte as u32 * 8 + offset) / bits_per_pixel
+    }
+
+    fn last_changed_pixel(&self, byte: usize, changed: u8) -> u32 {
+        let bits_per_pixel = ColorType::bits_per_pixel() as u32;
+        let offset = match self.bit_order.unwrap_or_else(|| self.layout.bit_order()) {
+            BitOrder::MsbFirst => 7 - changed.trailing_zeros(),
+            BitOrder::LsbFirst => 7 - changed.leading_zeros(),
+        };
+        (byte as u32 * 8 + offset) / bits_per_pixel
     }
 
     pub fn height(&self) -> u32 {
@@ -287,6 +633,46 @@ where
     }
 }
 
+/// Rectangular area of a buffer which was changed by `Frame::draw_part_dirty()`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct DirtyRect {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl DirtyRect {
+    pub fn union(self, other: DirtyRect) -> DirtyRect {
+        DirtyRect {
+            left: min(self.left, other.left),
+            top: min(self.top, other.top),
+            right: max(self.right, other.right),
+            bottom: max(self.bottom, other.bottom),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> u32 {
+        self.bottom - self.top
+    }
+}
+
+fn reverse_pixels(byte: u8, bits_per_pixel: usize) -> u8 {
+    match bits_per_pixel {
+        1 => byte.reverse_bits(),
+        2 => {
+            let byte = byte.rotate_left(4);
+            ((byte & 0x33) << 2) | ((byte >> 2) & 0x33)
+        }
+        4 => byte.rotate_left(4),
+        _ => byte,
+    }
+}
+
 pub struct Rectangle<ColorType> {
     left: i32,
     top: i32,
@@ -321,11 +707,18 @@ where
     }
 }
 
+/// Single line of text.
+///
+/// By default, `x` is the left edge of the text and `y` is the top of the ascender. `align()`
+/// and `vertical_align()` select other points of the text which are placed at (`x`, `y`).
 pub struct Text<'a, ColorType, FontImage, StringType> {
     text: StringType,
     x: i32,
     y: i32,
-    x_align: i32,
+    align: TextAlignment,
+    y_align: i32,
+    max_width: Option<u32>,
+    ellipsis: &'a str,
     font: &'a Font<'a, FontImage>,
     color: ColorType,
 }
@@ -347,32 +740,276 @@ where
             text,
             x,
             y,
-            x_align: 0,
+            align: TextAlignment::Left,
+            y_align: 0,
+            max_width: None,
+            ellipsis: ELLIPSIS,
             font,
             color,
         }
     }
 
     pub fn align(&mut self, align: TextAlignment) {
-        self.x_align = match align {
-            TextAlignment::Left => 0,
-            TextAlignment::Right => -(self.font.get_text_size(self.text.as_ref()).0 as i32),
-            TextAlignment::Center => -(self.font.get_text_size(self.text.as_ref()).0 as i32) / 2,
-        };
+        self.align = align;
+    }
+
+    pub fn vertical_align(&mut self, align: VerticalAlignment) {
+        self.y_align = align.offset(self.font.ascender as i32, self.font.descender as i32);
+    }
+
+    /// Truncates the text and appends the ellipsis if the text is wider than `width`.
+    pub fn max_width(&mut self, width: u32) {
+        self.max_width = Some(width);
+    }
+
+    /// Sets the suffix which is appended to truncated text ("…" by default).
+    ///
+    /// The font has to contain the glyphs of the suffix.
+    pub fn ellipsis(&mut self, ellipsis: &'a str) {
+        self.ellipsis = ellipsis;
     }
 
     pub fn draw(&self, clip: Clip, renderer: &mut Renderer<ColorType>) {
-        self.font.render(
-            renderer,
-            clip,
-            self.text.as_ref(),
-            self.x + self.x_align,
-            self.y,
-            self.color,
+        let text = self.text.as_ref();
+        let (text, suffix) = match self.max_width {
+            Some(width) if self.font.get_text_size(text).0 > width => (
+                self.font.truncate(text, width, self.ellipsis),
+                self.ellipsis,
+            ),
+            _ => (text, ""),
+        };
+        let y = self.y + self.y_align;
+        render_line(
+            self.font, renderer, clip, text, suffix, self.x, y, self.align, 0, self.color,
         );
     }
 }
 
+/// Part of a `RichText` with its own font and color.
+pub struct Span<'a, ColorType, FontImage> {
+    pub text: &'a str,
+    pub font: &'a Font<'a, FontImage>,
+    pub color: ColorType,
+}
+
+impl<'a, ColorType, FontImage> Span<'a, ColorType, FontImage> {
+    pub fn new(text: &'a str, font: &'a Font<FontImage>, color: ColorType) -> Self {
+        Self { text, font, color }
+    }
+}
+
+/// Single line of text consisting of spans with different fonts and colors, e.g., a large
+/// number followed by a small unit.
+///
+/// All spans share a baseline and are aligned as a unit like `Text`, using the largest
+/// ascender and descender of the spans.
+pub struct RichText<'a, ColorType, FontImage> {
+    spans: &'a [Span<'a, ColorType, FontImage>],
+    x: i32,
+    y: i32,
+    align: TextAlignment,
+    y_align: VerticalAlignment,
+}
+
+impl<'a, ColorType, FontImage> RichText<'a, ColorType, FontImage>
+where
+    ColorType: Color,
+    FontImage: MonoImageData,
+{
+    pub fn new(x: i32, y: i32, spans: &'a [Span<'a, ColorType, FontImage>]) -> Self {
+        Self {
+            spans,
+            x,
+            y,
+            align: TextAlignment::Left,
+            y_align: VerticalAlignment::Top,
+        }
+    }
+
+    pub fn align(&mut self, align: TextAlignment) {
+        self.align = align;
+    }
+
+    pub fn vertical_align(&mut self, align: VerticalAlignment) {
+        self.y_align = align;
+    }
+
+    /// Returns the width of all spans and the height from the largest ascender to the largest
+    /// descender.
+    pub fn get_size(&self) -> (u32, u32) {
+        let (ascender, descender) = self.get_line_metrics();
+        let width = self
+            .spans
+            .iter()
+            .map(|span| span.font.get_text_size(span.text).0)
+            .sum();
+        (width, (ascender + descender) as u32)
+    }
+
+    fn get_line_metrics(&self) -> (i32, i32) {
+        self.spans
+            .iter()
+            .fold((0, 0), |(ascender, descender), span| {
+                (
+                    max(ascender, span.font.ascender as i32),
+                    max(descender, span.font.descender as i32),
+                )
+            })
+    }
+
+    pub fn draw(&self, clip: Clip, renderer: &mut Renderer<ColorType>) {
+        let (ascender, descender) = self.get_line_metrics();
+        let width = self.get_size().0 as i32;
+        let mut x = self.x
+            - match self.align {
+                TextAlignment::Left => 0,
+                TextAlignment::Right => width,
+                TextAlignment::Center => width / 2,
+            };
+        let baseline = self.y + self.y_align.offset(ascender, descender) + ascender;
+        for span in self.spans {
+            let y = baseline - span.font.ascender as i32;
+            span.font
+                .render(renderer, clip, span.text, x, y, span.color);
+            x += span.font.get_text_size(span.text).0 as i32;
+        }
+    }
+}
+
+/// Text which is wrapped to fit into a box.
+///
+/// Lines which do not fit into the box vertically are clipped.
+pub struct TextBox<'a, ColorType, FontImage, StringType> {
+    text: StringType,
+    left: i32,
+    top: i32,
+    width: i32,
+    height: i32,
+    align: TextAlignment,
+    line_spacing: i32,
+    truncate: bool,
+    ellipsis: &'a str,
+    font: &'a Font<'a, FontImage>,
+    color: ColorType,
+}
+
+impl<'a, ColorType, FontImage, StringType> TextBox<'a, ColorType, FontImage, StringType>
+where
+    ColorType: Color,
+    FontImage: MonoImageData,
+    StringType: AsRef<str>,
+{
+    pub fn new(
+        left: i32,
+        top: i32,
+        width: i32,
+        height: i32,
+        text: StringType,
+        font: &'a Font<FontImage>,
+        color: ColorType,
+    ) -> Self {
+        Self {
+            text,
+            left,
+            top,
+            width,
+            height,
+            align: TextAlignment::Left,
+            line_spacing: 0,
+            truncate: false,
+            ellipsis: ELLIPSIS,
+            font,
+            color,
+        }
+    }
+
+    /// Sets the horizontal alignment of each line within the box.
+    pub fn align(&mut self, align: TextAlignment) {
+        self.align = align;
+    }
+
+    /// Sets the number of pixels between the descender of one line and the ascender of the next.
+    pub fn line_spacing(&mut self, spacing: i32) {
+        self.line_spacing = spacing;
+    }
+
+    /// If the text does not fit into the box, only draws the lines which fit completely and
+    /// appends the ellipsis to the last line.
+    pub fn truncate(&mut self, truncate: bool) {
+        self.truncate = truncate;
+    }
+
+    /// Sets the suffix which is appended to truncated text ("…" by default).
+    ///
+    /// The font has to contain the glyphs of the suffix.
+    pub fn ellipsis(&mut self, ellipsis: &'a str) {
+        self.ellipsis = ellipsis;
+    }
+
+    pub fn draw(&self, clip: Clip, renderer: &mut Renderer<ColorType>) {
+        let bottom = self.top + self.height;
+        let clip = clip.clip(self.left, self.top, self.left + self.width, bottom);
+        let font_height = (self.font.ascender + self.font.descender) as i32;
+        let line_height = font_height + self.line_spacing;
+        let width = max(self.width, 0) as u32;
+        let mut lines = self.font.lines(self.text.as_ref(), width).peekable();
+        let mut y = self.top;
+        while let Some(line) = lines.next() {
+            if y >= bottom {
+                break;
+            }
+            let last = y + line_height + font_height > bottom;
+            let (line, suffix) = if self.truncate && last && lines.peek().is_some() {
+                (
+                    self.font.truncate(line, width, self.ellipsis),
+                    self.ellipsis,
+                )
+            } else {
+                (line, "")
+            };
+            render_line(
+                self.font, renderer, clip, line, suffix, self.left, y, self.align, self.width,
+                self.color,
+            );
+            if self.truncate && last {
+                break;
+            }
+            y += line_height;
+        }
+    }
+}
+
+const ELLIPSIS: &str = "\u{2026}";
+
+/// Renders text followed by a suffix, aligned within the area starting at `x` with the width
+/// `width`.
+#[allow(clippy::too_many_arguments)]
+fn render_line<ColorType, FontImage>(
+    font: &Font<FontImage>,
+    renderer: &mut Renderer<ColorType>,
+    clip: Clip,
+    text: &str,
+    suffix: &str,
+    x: i32,
+    y: i32,
+    align: TextAlignment,
+    width: i32,
+    color: ColorType,
+) where
+    ColorType: Color,
+    FontImage: MonoImageData,
+{
+    let text_width = font.get_text_size(text).0 as i32;
+    let total_width = text_width + font.get_text_size(suffix).0 as i32;
+    let x = x + match align {
+        TextAlignment::Left => 0,
+        TextAlignment::Right => width - total_width,
+        TextAlignment::Center => (width - total_width) / 2,
+    };
+    font.render(renderer, clip, text, x, y, color);
+    font.render(renderer, clip, suffix, x + text_width, y, color);
+}
+
 pub struct MonoImage<'a, ImageType, ColorType> {
     image: &'a ImageType,
     x: i32,
@@ -402,10 +1039,37 @@ pub enum TextAlignment {
     Center,
 }
 
+/// Vertical position of the anchor point within a line of text.
+#[derive(Copy, Clone, PartialEq)]
+pub enum VerticalAlignment {
+    /// Top of the ascender.
+    Top,
+    /// Middle between the top of the ascender and the bottom of the descender.
+    Middle,
+    /// Baseline, shared by fonts of different sizes.
+    Baseline,
+    /// Bottom edge of the descender, i.e., the text ends in the row above the anchor point.
+    ///
+    /// Unlike `Baseline::Bottom` of embedded-graphics, the anchor row itself is not covered.
+    Bottom,
+}
+
+impl VerticalAlignment {
+    /// Returns the offset from the anchor point to the top of the ascender.
+    fn offset(self, ascender: i32, descender: i32) -> i32 {
+        match self {
+            VerticalAlignment::Top => 0,
+            VerticalAlignment::Middle => -(ascender + descender) / 2,
+            VerticalAlignment::Baseline => -ascender,
+            VerticalAlignment::Bottom => -(ascender + descender),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::color::BlackWhite::{self, Black, White};
-    use super::{Frame, Renderer};
+    use super::{BitOrder, DirtyRect, Frame, Layout, Polarity, Renderer};
 
     /*#[test]
     #[should_panic]
@@ -508,4 +1172,358 @@ mod tests {
             assert_eq!(buffer, test.ok);
         }
     }
+
+    #[test]
+    fn test_bitmap_row_clip() {
+        let mut buffer = [0u8; 2];
+        Frame::new(16, 1, |mut renderer| {
+            let clip = renderer.full_frame().clip_right(8);
+            renderer.render_bitmap_row(clip, 0, 0, 16, &[0xff, 0xff], White);
+        })
+        .draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0xff, 0x00]);
+    }
+
+    #[test]
+    fn test_page_layout_fill() {
+        let mut buffer = [0u8; 8];
+        let mut frame = Frame::new(4, 16, |mut renderer| {
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 1, 3, 3, 10, White);
+            renderer.fill(clip, 2, 4, 3, 5, Black);
+        });
+        frame.layout(Layout::Pages);
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x00, 0xf8, 0xe8, 0x00, 0x00, 0x03, 0x03, 0x00]);
+
+        // Only the second page.
+        let mut buffer = [0u8; 4];
+        frame.draw_part(8, &mut buffer);
+        assert_eq!(buffer, [0x00, 0x03, 0x03, 0x00]);
+
+        frame.mirror_x(true);
+        frame.mirror_y(true);
+        let mut buffer = [0u8; 8];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x00, 0xc0, 0xc0, 0x00, 0x00, 0x17, 0x1f, 0x00]);
+    }
+
+    #[test]
+    fn test_page_layout_bitmap() {
+        let mut buffer = [0u8; 10];
+        let mut frame = Frame::new(10, 8, |mut renderer| {
+            let clip = renderer.full_frame().clip_right(8);
+            renderer.render_bitmap_row(clip, 1, 2, 12, &[0xa5, 0xc0], White);
+            renderer.render_bitmap_row(clip, 2, 2, 12, &[0x80, 0x00], White);
+        });
+        frame.layout(Layout::Pages);
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0, 0, 0x06, 0, 0x02, 0, 0, 0x02, 0, 0]);
+
+        // Runs spanning bytes, empty bytes, clipping at the left edge and clearing.
+        let mut buffer = [0u8; 16];
+        let mut frame = Frame::new(16, 8, |mut renderer| {
+            let clip = renderer.full_frame();
+            renderer.render_bitmap_row(clip, 0, -4, 20, &[0x00, 0x3c, 0xff], White);
+            renderer.fill(clip, 0, 1, 16, 2, White);
+            renderer.render_bitmap_row(clip, 1, 0, 8, &[0x0f], Black);
+        });
+        frame.layout(Layout::Pages);
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [2, 2, 2, 2, 0, 0, 1, 1, 3, 3, 2, 2, 3, 3, 3, 3]);
+    }
+
+    #[test]
+    fn test_buffer_format() {
+        let mut frame = Frame::new(12, 2, |mut renderer| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 1, 0, 4, 1, White);
+            renderer.fill(clip, 8, 1, 10, 2, White);
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x70, 0x00, 0x00, 0xc0]);
+
+        frame.bit_order(BitOrder::LsbFirst);
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x0e, 0x00, 0x00, 0x03]);
+
+        frame.polarity(Polarity::Inverted);
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0xf1, 0xff, 0xff, 0xfc]);
+
+        frame.polarity(Polarity::Normal);
+        frame.bit_order(BitOrder::MsbFirst);
+        frame.row_alignment(4);
+        assert_eq!(frame.stride(), 4);
+        let mut buffer = [0u8; 8];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0x70, 0x00, 0x00, 0x00, 0x00, 0xc0, 0x00, 0x00]);
+
+        frame.layout(Layout::Pages);
+        frame.bit_order(BitOrder::MsbFirst);
+        assert_eq!(frame.stride(), 12);
+        let mut buffer = [0u8; 12];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0, 0x80, 0x80, 0x80, 0, 0, 0, 0, 0x40, 0x40, 0, 0]);
+    }
+
+    #[test]
+    fn test_dirty_rect() {
+        let frame = Frame::new(32, 4, |mut renderer| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 11, 1, 13, 3, White);
+        });
+        let previous = [0u8; 16];
+        let mut buffer = [0u8; 16];
+        assert_eq!(
+            frame.draw_part_dirty(0, &mut buffer, &previous, 1),
+            Some(DirtyRect {
+                left: 11,
+                top: 1,
+                right: 13,
+                bottom: 3
+            })
+        );
+        assert_eq!(
+            frame.draw_part_dirty(0, &mut buffer, &previous, 8),
+            Some(DirtyRect {
+                left: 8,
+                top: 1,
+                right: 16,
+                bottom: 3
+            })
+        );
+        let previous = buffer;
+        assert_eq!(frame.draw_part_dirty(0, &mut buffer, &previous, 8), None);
+
+        // Bands only cover a part of the change.
+        let previous = [0u8; 4];
+        let mut buffer = [0u8; 4];
+        assert_eq!(
+            frame.draw_part_dirty(2, &mut buffer, &previous, 1),
+            Some(DirtyRect {
+                left: 11,
+                top: 2,
+                right: 13,
+                bottom: 3
+            })
+        );
+
+        // The aligned bounds are limited to the frame.
+        let frame = Frame::new(12, 1, |mut renderer| {
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 9, 0, 11, 1, White);
+        });
+        let previous = [0u8; 2];
+        let mut buffer = [0u8; 2];
+        assert_eq!(
+            frame.draw_part_dirty(0, &mut buffer, &previous, 8),
+            Some(DirtyRect {
+                left: 8,
+                top: 0,
+                right: 12,
+                bottom: 1
+            })
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dirty_rect_zero_align() {
+        let frame = Frame::new(8, 1, |_: Renderer<BlackWhite>| {});
+        frame.draw_part_dirty(0, &mut [0u8], &[0u8], 0);
+    }
+
+    #[test]
+    fn test_frame_diff() {
+        let mut frame = Frame::new(16, 16, |mut renderer| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            renderer.fill(clip, 2, 9, 5, 10, White);
+        });
+        frame.layout(Layout::Pages);
+        frame.row_alignment(4);
+        let old = [0u8; 32];
+        let mut new = [0u8; 32];
+        frame.draw_part(0, &mut new);
+        let mut diff = frame.diff(0, &old, &new);
+        let change = diff.next().unwrap();
+        assert_eq!(change.rows, 8..16);
+        assert_eq!(change.bytes, 2..5);
+        assert_eq!(diff.next(), None);
+    }
+
+    #[test]
+    fn test_pixel_access() {
+        for &layout in &[Layout::Rows, Layout::Pages] {
+            let mut frame = Frame::new(10, 8, |mut renderer: Renderer<BlackWhite>| {
+                renderer.clear(Black);
+                let clip = renderer.full_frame();
+                renderer.set_pixel(clip, 9, 2, White);
+                renderer.set_pixel(clip, 10, 2, White);
+                renderer.set_pixel(clip.clip_right(3), 3, 5, White);
+                assert_eq!(renderer.get_pixel(9, 2), Some(White));
+                assert_eq!(renderer.get_pixel(8, 2), Some(Black));
+                assert_eq!(renderer.get_pixel(3, 5), Some(Black));
+                assert_eq!(renderer.get_pixel(10, 2), None);
+            });
+            frame.layout(layout);
+            frame.mirror_y(true);
+            let mut buffer = [0u8; 16];
+            frame.draw_part(0, &mut buffer);
+        }
+    }
+
+    #[test]
+    fn test_text_box() {
+        use super::font::{test_font, test_glyph, Font};
+        use super::image::MonoBitmapImage;
+        use super::{TextAlignment, TextBox};
+
+        const FONT: Font<MonoBitmapImage> =
+            test_font(1, 0, &[test_glyph(&[0x80], 1, 0, 1, 2)], |c| {
+                if c == 'a' {
+                    Some(0)
+                } else {
+                    None
+                }
+            });
+
+        let frame = Frame::new(8, 6, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            let mut text = TextBox::new(1, 0, 6, 5, "aa aaaa\na", &FONT, White);
+            text.align(TextAlignment::Right);
+            text.line_spacing(1);
+            text.draw(clip, &mut renderer);
+        });
+        let mut buffer = [0u8; 6];
+        frame.draw_part(0, &mut buffer);
+        // The last line is outside of the box.
+        assert_eq!(buffer, [0x14, 0x00, 0x54, 0x00, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn test_vertical_alignment() {
+        use super::font::{test_font, test_glyph, Font};
+        use super::image::MonoBitmapImage;
+        use super::{Text, VerticalAlignment};
+
+        // Glyph which covers the whole line, from the ascender to the descender.
+        const FONT: Font<MonoBitmapImage> =
+            test_font(3, 1, &[test_glyph(&[0x80; 4], 1, 0, 3, 1)], |_| Some(0));
+
+        let alignments = [
+            (VerticalAlignment::Top, 0xf0),
+            (VerticalAlignment::Middle, 0x3c),
+            (VerticalAlignment::Baseline, 0x1e),
+            (VerticalAlignment::Bottom, 0x0f),
+        ];
+        for &(align, column) in alignments.iter() {
+            let frame = Frame::new(1, 8, |mut renderer: Renderer<BlackWhite>| {
+                renderer.clear(Black);
+                let clip = renderer.full_frame();
+                let mut text = Text::new(0, 4, "a", &FONT, White);
+                text.vertical_align(align);
+                text.draw(clip, &mut renderer);
+            });
+            let mut buffer = [0u8; 8];
+            frame.draw_part(0, &mut buffer);
+            let rendered = buffer
+                .iter()
+                .enumerate()
+                .fold(0u8, |column, (y, &row)| column | ((row >> 7) << y));
+            assert_eq!(rendered, column);
+        }
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        use super::font::{test_font, test_glyph, Font};
+        use super::image::MonoBitmapImage;
+        use super::{Text, TextAlignment, TextBox};
+
+        const FONT: Font<MonoBitmapImage> = test_font(
+            1,
+            0,
+            &[
+                test_glyph(&[0x80], 1, 0, 1, 2),
+                test_glyph(&[0xc0], 2, 0, 1, 2),
+            ],
+            |c| match c {
+                'a' => Some(0),
+                '.' => Some(1),
+                _ => None,
+            },
+        );
+
+        let frame = Frame::new(8, 4, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            for (y, &width) in [8, 7].iter().enumerate() {
+                let mut text = Text::new(0, y as i32, "aaaa", &FONT, White);
+                text.max_width(width);
+                text.ellipsis(".");
+                text.draw(clip, &mut renderer);
+            }
+            let mut text = Text::new(8, 2, "aaaa", &FONT, White);
+            text.align(TextAlignment::Right);
+            text.max_width(7);
+            text.ellipsis(".");
+            text.draw(clip, &mut renderer);
+        });
+        let mut buffer = [0u8; 4];
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0xaa, 0xac, 0x2b, 0x00]);
+
+        let frame = Frame::new(8, 4, |mut renderer: Renderer<BlackWhite>| {
+            renderer.clear(Black);
+            let clip = renderer.full_frame();
+            let mut text = TextBox::new(0, 0, 4, 3, "aa aa aa aa", &FONT, White);
+            text.truncate(true);
+            text.ellipsis(".");
+            text.draw(clip, &mut renderer);
+        });
+        frame.draw_part(0, &mut buffer);
+        assert_eq!(buffer, [0xa0, 0xa0, 0xb0, 0x00]);
+    }
+
+    #[test]
+    fn test_rich_text() {
+        use super::font::{test_font, test_glyph, Font};
+        use super::image::MonoBitmapImage;
+        use super::{RichText, Span, TextAlignment, VerticalAlignment};
+
+        // Glyph which covers the whole line, from the ascender to the descender.
+        const LARGE: Font<MonoBitmapImage> =
+            test_font(2, 1, &[test_glyph(&[0x80; 3], 1, 0, 2, 2)], |_| Some(0));
+        const SMALL: Font<MonoBitmapImage> =
+            test_font(1, 0, &[test_glyph(&[0x80], 1, 0, 1, 1)], |_| Some(0));
+
+        let spans = [Span::new("1", &LARGE, White), Span::new("C", &SMALL, White)];
+        assert_eq!(RichText::new(0, 0, &spans).get_size(), (3, 3));
+
+        let alignments = [
+            (VerticalAlignment::Top, 0, [0x04, 0x05, 0x04, 0x00]),
+            (VerticalAlignment::Bottom, 4, [0x00, 0x04, 0x05, 0x04]),
+        ];
+        for &(align, y, expected) in alignments.iter() {
+            let frame = Frame::new(8, 4, |mut renderer: Renderer<BlackWhite>| {
+                renderer.clear(Black);
+                let clip = renderer.full_frame();
+                let mut text = RichText::new(8, y, &spans);
+                text.align(TextAlignment::Right);
+                text.vertical_align(align);
+                text.draw(clip, &mut renderer);
+            });
+            let mut buffer = [0u8; 4];
+            frame.draw_part(0, &mut buffer);
+            assert_eq!(buffer, expected);
+        }
+    }
 }
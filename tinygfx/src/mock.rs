@@ -0,0 +1,79 @@
+//! Display interface which records all transfers instead of sending them to a display, so that
+//! the sequence of commands emitted by the drivers can be tested without hardware.
+
+extern crate std;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use super::epd::Busy;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Transfer {
+    Command(Vec<u8>),
+    Data(Vec<u8>),
+    /// The driver waited for the BUSY signal via `RecordingBusy`.
+    WaitBusy,
+}
+
+#[derive(Clone, Default)]
+pub struct RecordingInterface {
+    transfers: Rc<RefCell<Vec<Transfer>>>,
+}
+
+impl RecordingInterface {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a `Busy` implementation which records the waits in the same list as the
+    /// transfers.
+    pub fn busy(&self) -> RecordingBusy {
+        RecordingBusy {
+            transfers: self.transfers.clone(),
+        }
+    }
+
+    pub fn transfers(&self) -> Vec<Transfer> {
+        self.transfers.borrow().clone()
+    }
+
+    pub fn clear(&mut self) {
+        self.transfers.borrow_mut().clear();
+    }
+}
+
+fn to_bytes(data: DataFormat<'_>) -> Result<Vec<u8>, DisplayError> {
+    match data {
+        DataFormat::U8(data) => Ok(data.to_vec()),
+        DataFormat::U8Iter(data) => Ok(data.collect()),
+        _ => Err(DisplayError::DataFormatNotImplemented),
+    }
+}
+
+impl WriteOnlyDataCommand for RecordingInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let cmd = to_bytes(cmd)?;
+        self.transfers.borrow_mut().push(Transfer::Command(cmd));
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let buf = to_bytes(buf)?;
+        self.transfers.borrow_mut().push(Transfer::Data(buf));
+        Ok(())
+    }
+}
+
+pub struct RecordingBusy {
+    transfers: Rc<RefCell<Vec<Transfer>>>,
+}
+
+impl Busy for RecordingBusy {
+    fn wait(&mut self) {
+        self.transfers.borrow_mut().push(Transfer::WaitBusy);
+    }
+}